@@ -20,16 +20,30 @@ impl Interval {
         }
     }
 
-    pub fn clamp(&self, x: f64) -> f64 {
-        if x < self.min {
-            self.min
-        } else if x > self.max {
-            self.max
-        } else {
-            x
+    pub fn universe() -> Interval {
+        Interval {
+            min: f64::NEG_INFINITY,
+            max: f64::INFINITY,
         }
     }
 
+    pub fn surrounding(a: &Interval, b: &Interval) -> Interval {
+        Interval::new(a.min.min(b.min), a.max.max(b.max))
+    }
+
+    // Empty when the two don't meet.
+    pub fn overlap(a: &Interval, b: &Interval) -> Interval {
+        Interval::new(a.min.max(b.min), a.max.min(b.max))
+    }
+
+    pub fn size(&self) -> f64 {
+        self.max - self.min
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.min.is_finite() && self.max.is_finite()
+    }
+
     pub fn expand(&self, delta: f64) -> Interval {
         let padding = delta / 2.0;
         Interval::new(self.min - padding, self.max + padding)
@@ -42,7 +56,7 @@ impl Default for Interval {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Default)]
 pub struct BBox {
     pub x: Interval,
     pub y: Interval,
@@ -54,6 +68,11 @@ impl BBox {
         BBox { x, y, z }
     }
 
+    // Bounds of objects, such as infinite planes, that no finite box can enclose.
+    pub fn universe() -> BBox {
+        BBox::new(Interval::universe(), Interval::universe(), Interval::universe())
+    }
+
     pub fn from_points(a: Vec3, b: Vec3) -> BBox {
         let x = if a[0] <= b[0] {
             Interval::new(a[0] as f64, b[0] as f64)
@@ -73,6 +92,55 @@ impl BBox {
         BBox { x, y, z }
     }
 
+    pub fn surrounding(a: &BBox, b: &BBox) -> BBox {
+        BBox {
+            x: Interval::surrounding(&a.x, &b.x),
+            y: Interval::surrounding(&a.y, &b.y),
+            z: Interval::surrounding(&a.z, &b.z),
+        }
+    }
+
+    pub fn overlap(a: &BBox, b: &BBox) -> BBox {
+        BBox {
+            x: Interval::overlap(&a.x, &b.x),
+            y: Interval::overlap(&a.y, &b.y),
+            z: Interval::overlap(&a.z, &b.z),
+        }
+    }
+
+    // Widens zero-thickness sides so flat primitives still have a box rays can enter.
+    pub fn pad(&self) -> BBox {
+        let delta = 0.0001;
+        let pad = |ax: &Interval| if ax.size() < delta { ax.expand(delta) } else { *ax };
+        BBox { x: pad(&self.x), y: pad(&self.y), z: pad(&self.z) }
+    }
+
+    pub fn is_bounded(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
+    }
+
+    pub fn centroid(&self, axis: usize) -> f64 {
+        let ax = &self[axis];
+        0.5 * (ax.min + ax.max)
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let dx = self.x.size().max(0.0);
+        let dy = self.y.size().max(0.0);
+        let dz = self.z.size().max(0.0);
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        if self.x.size() > self.y.size() {
+            if self.x.size() > self.z.size() { 0 } else { 2 }
+        } else if self.y.size() > self.z.size() {
+            1
+        } else {
+            2
+        }
+    }
+
     pub fn hit(&self, r: &Ray, ray_t: Interval) -> bool {
         let mut ray_t = ray_t;
         for axis in 0..3 {
@@ -98,16 +166,6 @@ impl BBox {
     }
 }
 
-impl Default for BBox {
-    fn default() -> Self {
-        BBox {
-            x: Interval::default(),
-            y: Interval::default(),
-            z: Interval::default(),
-        }
-    }
-}
-
 impl ops::Index<usize> for BBox {
     type Output = Interval;
     fn index(&self, index: usize) -> &Self::Output {
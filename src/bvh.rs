@@ -0,0 +1,540 @@
+use std::fmt;
+use crate::bounds::{BBox, Interval};
+use crate::hits::{HitRecord, Hittable, HittableList};
+use crate::instance::Instance;
+use crate::ray::Ray;
+
+const SAH_BINS: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+const TRAVERSAL_COST: f64 = 1.0;
+const STACK_SIZE: usize = 64;
+
+#[derive(Copy, Clone)]
+struct LinearNode {
+    bbox: BBox,
+    // Leaves: index of the first primitive. Interior nodes: index of the second child,
+    // the first child always directly follows its parent.
+    offset: usize,
+    count: usize,
+    axis: usize,
+}
+
+#[derive(Default)]
+pub struct BvhStats {
+    pub node_count: usize,
+    pub leaf_count: usize,
+    pub depth: usize,
+    pub leaf_sizes: Vec<usize>,
+}
+
+impl BvhStats {
+    fn record_leaf(&mut self, size: usize, depth: usize) {
+        if self.leaf_sizes.len() <= size {
+            self.leaf_sizes.resize(size + 1, 0);
+        }
+        self.leaf_sizes[size] += 1;
+        self.leaf_count += 1;
+        self.depth = self.depth.max(depth);
+    }
+}
+
+impl fmt::Display for BvhStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} nodes ({} leaves), depth {}, leaf sizes:", self.node_count, self.leaf_count, self.depth)?;
+        for (size, count) in self.leaf_sizes.iter().enumerate() {
+            if *count > 0 {
+                write!(f, " {}x{}", count, size)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+struct Bin {
+    bbox: BBox,
+    count: usize,
+}
+
+struct Builder<'a> {
+    bounds: &'a [BBox],
+    centroids: Vec<[f64; 3]>,
+    nodes: Vec<LinearNode>,
+    stats: BvhStats,
+}
+
+impl<'a> Builder<'a> {
+    fn new(bounds: &'a [BBox]) -> Builder<'a> {
+        let centroids = bounds
+            .iter()
+            .map(|b| [b.centroid(0), b.centroid(1), b.centroid(2)])
+            .collect();
+
+        Builder {
+            bounds,
+            centroids,
+            nodes: Vec::with_capacity(2 * bounds.len()),
+            stats: BvhStats::default(),
+        }
+    }
+
+    fn push_leaf(&mut self, bbox: BBox, offset: usize, count: usize, depth: usize) {
+        self.nodes.push(LinearNode { bbox, offset, count, axis: 0 });
+        self.stats.record_leaf(count, depth);
+    }
+
+    // Builds the subtree for `indices`, which start at position `offset` of the final
+    // primitive ordering, and appends its nodes in depth-first order.
+    fn build(&mut self, indices: &mut [usize], offset: usize, depth: usize) {
+        let bbox = indices
+            .iter()
+            .fold(BBox::default(), |bbox, &i| BBox::surrounding(&bbox, &self.bounds[i]));
+        let count = indices.len();
+
+        if count <= 1 {
+            self.push_leaf(bbox, offset, count, depth);
+            return;
+        }
+
+        let centroid_bounds = indices.iter().fold(BBox::default(), |cb, &i| {
+            let c = self.centroids[i];
+            BBox::surrounding(&cb, &BBox::new(
+                Interval::new(c[0], c[0]),
+                Interval::new(c[1], c[1]),
+                Interval::new(c[2], c[2]),
+            ))
+        });
+        let axis = centroid_bounds.longest_axis();
+        let extent = centroid_bounds[axis];
+
+        if extent.size() <= 0.0 {
+            // All centroids coincide, so no binning can separate them.
+            if count <= MAX_LEAF_SIZE {
+                self.push_leaf(bbox, offset, count, depth);
+            } else {
+                self.split(indices, offset, depth, bbox, axis, count / 2);
+            }
+            return;
+        }
+
+        if depth >= STACK_SIZE / 2 {
+            // Bound the tree depth so traversal never overflows its stack: from here on
+            // median splits halve the primitive count at every level.
+            let mid = count / 2;
+            let centroids = &self.centroids;
+            indices.select_nth_unstable_by(mid, |&a, &b| centroids[a][axis].total_cmp(&centroids[b][axis]));
+            self.split(indices, offset, depth, bbox, axis, mid);
+            return;
+        }
+
+        let bin_of = |c: f64| {
+            let b = ((c - extent.min) / extent.size() * SAH_BINS as f64) as usize;
+            b.min(SAH_BINS - 1)
+        };
+
+        let mut bins = [Bin::default(); SAH_BINS];
+        for &i in indices.iter() {
+            let bin = &mut bins[bin_of(self.centroids[i][axis])];
+            bin.count += 1;
+            bin.bbox = BBox::surrounding(&bin.bbox, &self.bounds[i]);
+        }
+
+        // right_area[s] / right_count[s] describe bins s+1.. for a split after bin s.
+        let mut right_area = [0.0; SAH_BINS - 1];
+        let mut right_count = [0; SAH_BINS - 1];
+        let mut acc = Bin::default();
+        for s in (0..SAH_BINS - 1).rev() {
+            acc.bbox = BBox::surrounding(&acc.bbox, &bins[s + 1].bbox);
+            acc.count += bins[s + 1].count;
+            right_area[s] = acc.bbox.surface_area();
+            right_count[s] = acc.count;
+        }
+
+        let mut best_split = 0;
+        let mut best_cost = f64::INFINITY;
+        let mut acc = Bin::default();
+        for s in 0..SAH_BINS - 1 {
+            acc.bbox = BBox::surrounding(&acc.bbox, &bins[s].bbox);
+            acc.count += bins[s].count;
+            if acc.count == 0 || right_count[s] == 0 {
+                continue;
+            }
+            let cost = acc.count as f64 * acc.bbox.surface_area() + right_count[s] as f64 * right_area[s];
+            if cost < best_cost {
+                best_cost = cost;
+                best_split = s;
+            }
+        }
+
+        let area = bbox.surface_area();
+        let split_cost = if area > 0.0 { TRAVERSAL_COST + best_cost / area } else { f64::INFINITY };
+        let leaf_cost = count as f64;
+
+        if count <= MAX_LEAF_SIZE && leaf_cost <= split_cost {
+            self.push_leaf(bbox, offset, count, depth);
+            return;
+        }
+
+        let mut mid = partition(indices, |&i| bin_of(self.centroids[i][axis]) <= best_split);
+        if mid == 0 || mid == count {
+            mid = count / 2;
+        }
+        self.split(indices, offset, depth, bbox, axis, mid);
+    }
+
+    fn split(&mut self, indices: &mut [usize], offset: usize, depth: usize, bbox: BBox, axis: usize, mid: usize) {
+        let node = self.nodes.len();
+        self.nodes.push(LinearNode { bbox, offset: 0, count: 0, axis });
+
+        let (left, right) = indices.split_at_mut(mid);
+        self.build(left, offset, depth + 1);
+        self.nodes[node].offset = self.nodes.len();
+        self.build(right, offset + mid, depth + 1);
+    }
+}
+
+fn partition<T, F: Fn(&T) -> bool>(items: &mut [T], pred: F) -> usize {
+    let mut first = 0;
+    for i in 0..items.len() {
+        if pred(&items[i]) {
+            items.swap(first, i);
+            first += 1;
+        }
+    }
+    first
+}
+
+// Returns the primitive order and the flattened nodes, laid out depth-first.
+fn build_nodes(bounds: &[BBox]) -> (Vec<usize>, Vec<LinearNode>, BvhStats) {
+    let mut order: Vec<usize> = (0..bounds.len()).collect();
+    let mut builder = Builder::new(bounds);
+    builder.build(&mut order, 0, 1);
+
+    builder.stats.node_count = builder.nodes.len();
+    (order, builder.nodes, builder.stats)
+}
+
+fn traverse<'a, F>(nodes: &[LinearNode], ray: &Ray, t_min: f32, t_max: f32, mut hit_leaf: F) -> Option<HitRecord<'a>>
+where
+    F: FnMut(usize, f32) -> Option<HitRecord<'a>>,
+{
+    let mut closest: Option<HitRecord<'a>> = None;
+    let mut closest_so_far = t_max;
+    let mut stack = [0usize; STACK_SIZE];
+    let mut top = 0;
+    let mut current = 0;
+
+    loop {
+        let node = &nodes[current];
+        if node.bbox.hit(ray, Interval::new(t_min as f64, closest_so_far as f64)) {
+            if node.count > 0 {
+                for i in node.offset..node.offset + node.count {
+                    if let Some(hit) = hit_leaf(i, closest_so_far) {
+                        closest_so_far = hit.t;
+                        closest = Some(hit);
+                    }
+                }
+            } else {
+                // Visit the child on the near side of the split plane first.
+                let (near, far) = if ray.direction[node.axis] < 0.0 {
+                    (node.offset, current + 1)
+                } else {
+                    (current + 1, node.offset)
+                };
+                stack[top] = far;
+                top += 1;
+                current = near;
+                continue;
+            }
+        }
+
+        if top == 0 {
+            break;
+        }
+        top -= 1;
+        current = stack[top];
+    }
+
+    closest
+}
+
+pub struct Bvh {
+    objects: Vec<Box<dyn Hittable>>,
+    // Objects without finite bounds are kept out of the tree and tested against every ray.
+    unbounded: Vec<Box<dyn Hittable>>,
+    nodes: Vec<LinearNode>,
+    stats: BvhStats,
+}
+
+impl Bvh {
+    pub fn new(list: HittableList) -> Bvh {
+        let (bounded, unbounded): (Vec<_>, Vec<_>) = list
+            .objects
+            .into_iter()
+            .partition(|object| object.bounding_box().is_bounded());
+
+        let bounds: Vec<BBox> = bounded.iter().map(|object| object.bounding_box()).collect();
+        let (order, nodes, stats) = build_nodes(&bounds);
+
+        let mut slots: Vec<Option<Box<dyn Hittable>>> = bounded.into_iter().map(Some).collect();
+        let objects = order.iter().map(|&i| slots[i].take().unwrap()).collect();
+
+        Bvh { objects, unbounded, nodes, stats }
+    }
+
+    pub fn stats(&self) -> &BvhStats {
+        &self.stats
+    }
+}
+
+impl Hittable for Bvh {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let mut hit_anything = None;
+        let mut closest_so_far = t_max;
+
+        for object in &self.unbounded {
+            if let Some(hit) = object.hit(ray, t_min, closest_so_far) {
+                closest_so_far = hit.t;
+                hit_anything = Some(hit);
+            }
+        }
+
+        if self.objects.is_empty() {
+            return hit_anything;
+        }
+        traverse(&self.nodes, ray, t_min, closest_so_far, |i, closest_so_far| {
+            self.objects[i].hit(ray, t_min, closest_so_far)
+        })
+        .or(hit_anything)
+    }
+
+    fn bounding_box(&self) -> BBox {
+        if !self.unbounded.is_empty() {
+            return BBox::universe();
+        }
+        self.nodes.first().map_or(BBox::default(), |root| root.bbox)
+    }
+}
+
+// Top level of a two-level hierarchy: a BVH over instances, each of which points at a
+// bottom-level structure (typically a mesh's own `Bvh`) shared through an `Arc`. It is built
+// over one box per instance and never looks inside the shared structures.
+pub struct Tlas {
+    instances: Vec<Instance>,
+    // Tree order of the bounded instances; leaves index into this.
+    order: Vec<usize>,
+    unbounded: Vec<usize>,
+    nodes: Vec<LinearNode>,
+    stats: BvhStats,
+}
+
+impl Tlas {
+    pub fn new(instances: Vec<Instance>) -> Tlas {
+        let (bounded, unbounded): (Vec<usize>, Vec<usize>) =
+            (0..instances.len()).partition(|&i| instances[i].bounding_box().is_bounded());
+
+        let bounds: Vec<BBox> = bounded.iter().map(|&i| instances[i].bounding_box()).collect();
+        let (order, nodes, stats) = build_nodes(&bounds);
+        let order = order.iter().map(|&i| bounded[i]).collect();
+
+        Tlas { instances, order, unbounded, nodes, stats }
+    }
+
+    pub fn stats(&self) -> &BvhStats {
+        &self.stats
+    }
+}
+
+impl Hittable for Tlas {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let mut hit_anything = None;
+        let mut closest_so_far = t_max;
+
+        for &i in &self.unbounded {
+            if let Some(hit) = self.instances[i].hit(ray, t_min, closest_so_far) {
+                closest_so_far = hit.t;
+                hit_anything = Some(hit);
+            }
+        }
+
+        if self.order.is_empty() {
+            return hit_anything;
+        }
+        traverse(&self.nodes, ray, t_min, closest_so_far, |i, closest_so_far| {
+            self.instances[self.order[i]].hit(ray, t_min, closest_so_far)
+        })
+        .or(hit_anything)
+    }
+
+    fn bounding_box(&self) -> BBox {
+        if !self.unbounded.is_empty() {
+            return BBox::universe();
+        }
+        self.nodes.first().map_or(BBox::default(), |root| root.bbox)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::hits::HittableList;
+    use crate::material::Lambertian;
+    use crate::plane::Plane;
+    use crate::random;
+    use crate::sphere::Sphere;
+    use crate::transform::{Mat4, Transform};
+    use crate::vec3::Vec3;
+
+    // Instances of one shared sphere scattered by `seed`, plus a plane instance with no bounds.
+    fn instances(seed: u64) -> Vec<Instance> {
+        let sphere: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0, gray()));
+        let plane: Arc<dyn Hittable> = Arc::new(Plane::new(Vec3::new(0.0, -12.0, 0.0), Vec3::new(0.0, 1.0, 0.0), gray()));
+        random::seed(seed);
+        let mut instances: Vec<Instance> = (0..200)
+            .map(|_| {
+                let translate = Vec3::random_range(-10.0, 10.0);
+                let rotate = Vec3::random_range(0.0, 360.0);
+                let scale = random::random_range(0.1, 1.0);
+                let transform = Transform::from_trs(translate, rotate, Vec3::new(scale, scale, scale)).unwrap();
+                Instance::new(sphere.clone(), transform)
+            })
+            .collect();
+        instances.push(Instance::new(plane, Transform::new(Mat4::identity()).unwrap()));
+        instances
+    }
+
+    #[test]
+    fn instance_hits_match_a_linear_scan() {
+        let tlas = Tlas::new(instances(17));
+        let mut list = HittableList::new();
+        for instance in instances(17) {
+            list.add(Box::new(instance));
+        }
+        assert_eq!(tlas.stats().leaf_sizes.iter().enumerate().map(|(size, n)| size * n).sum::<usize>(), 200);
+        assert!(!tlas.bounding_box().is_bounded());
+
+        random::seed(19);
+        for _ in 0..2000 {
+            let ray = Ray::new(Vec3::random_range(-12.0, 12.0), Vec3::random_unit_vector());
+            let expected = list.hit(&ray, 0.001, f32::INFINITY).map(|hit| (hit.t, hit.p));
+            assert_eq!(tlas.hit(&ray, 0.001, f32::INFINITY).map(|hit| (hit.t, hit.p)), expected);
+        }
+    }
+
+    fn gray() -> Box<Lambertian> {
+        Box::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))
+    }
+
+    // Builds the same scene every time for a given seed, so a list and a BVH can each own a copy.
+    fn random_spheres(seed: u64, count: usize) -> HittableList {
+        random::seed(seed);
+        let mut list = HittableList::new();
+        for _ in 0..count {
+            let center = Vec3::random_range(-10.0, 10.0);
+            list.add(Box::new(Sphere::new(center, random::random_range(0.05, 1.0), gray())));
+        }
+        list
+    }
+
+    // Asserts that `bvh` and a linear scan of `list` agree on the nearest hit of random rays.
+    fn assert_matches_list(bvh: &Bvh, list: &HittableList, seed: u64) {
+        random::seed(seed);
+        for _ in 0..2000 {
+            let ray = Ray::new(Vec3::random_range(-12.0, 12.0), Vec3::random_unit_vector());
+            let expected = list.hit(&ray, 0.001, f32::INFINITY).map(|hit| (hit.t, hit.p));
+            let actual = bvh.hit(&ray, 0.001, f32::INFINITY).map(|hit| (hit.t, hit.p));
+            assert_eq!(expected, actual);
+        }
+    }
+
+    #[test]
+    fn nearest_hit_matches_a_linear_scan() {
+        let bvh = Bvh::new(random_spheres(7, 500));
+        assert_matches_list(&bvh, &random_spheres(7, 500), 11);
+        assert!(bvh.stats().leaf_sizes.len() <= MAX_LEAF_SIZE + 1);
+    }
+
+    #[test]
+    fn unbounded_objects_stay_outside_the_tree() {
+        let with_planes = || {
+            let mut list = random_spheres(3, 100);
+            list.add(Box::new(Plane::new(Vec3::new(0.0, -5.0, 0.0), Vec3::new(0.0, 1.0, 0.0), gray())));
+            list.add(Box::new(Plane::new(Vec3::new(4.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0), gray())));
+            list
+        };
+        let bvh = Bvh::new(with_planes());
+        assert_matches_list(&bvh, &with_planes(), 5);
+        assert!(!bvh.bounding_box().is_bounded());
+
+        // A tree holding nothing but planes still finds them.
+        let mut planes = HittableList::new();
+        planes.add(Box::new(Plane::new(Vec3::new(0.0, 0.0, 2.0), Vec3::new(0.0, 0.0, 1.0), gray())));
+        let bvh = Bvh::new(planes);
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!((bvh.hit(&ray, 0.001, f32::INFINITY).unwrap().t - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn empty_and_single_item_lists() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+
+        let empty = Bvh::new(HittableList::new());
+        assert!(empty.hit(&ray, 0.001, f32::INFINITY).is_none());
+        assert!(!empty.bounding_box().is_bounded());
+
+        let mut list = HittableList::new();
+        list.add(Box::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0, gray())));
+        let single = Bvh::new(list);
+        assert_eq!(single.stats().node_count, 1);
+        assert_eq!(single.stats().leaf_count, 1);
+        assert!((single.hit(&ray, 0.001, f32::INFINITY).unwrap().t - 4.0).abs() < 1e-6);
+        assert!(single.hit(&ray, 0.001, 3.0).is_none());
+    }
+
+    #[test]
+    fn coinciding_centroids_still_split() {
+        // Concentric spheres leave the binned split nothing to separate.
+        let concentric = || {
+            let mut list = HittableList::new();
+            for i in 0..40 {
+                list.add(Box::new(Sphere::new(Vec3::new(1.0, 2.0, 3.0), 0.1 + 0.1 * i as f32, gray())));
+            }
+            list
+        };
+        let bvh = Bvh::new(concentric());
+        assert!(bvh.stats().leaf_sizes.len() <= MAX_LEAF_SIZE + 1);
+        assert_eq!(bvh.stats().leaf_sizes.iter().enumerate().map(|(size, n)| size * n).sum::<usize>(), 40);
+        assert_matches_list(&bvh, &concentric(), 13);
+    }
+
+    #[test]
+    fn depth_stays_within_the_traversal_stack() {
+        // Spheres that double in distance and size make SAH peel off a few at a time, which
+        // would build a chain deeper than the traversal stack without the median fallback.
+        let chain = || {
+            let mut list = HittableList::new();
+            for i in -120..120 {
+                let x = 2f32.powi(i);
+                list.add(Box::new(Sphere::new(Vec3::new(x, 0.0, 0.0), 0.25 * x, gray())));
+            }
+            list
+        };
+        let bvh = Bvh::new(chain());
+        assert!(bvh.stats().depth < STACK_SIZE);
+
+        // Rays along the chain walk its deepest nodes.
+        let list = chain();
+        for i in -120..120 {
+            let x = 2f32.powi(i);
+            for ray in [
+                Ray::new(Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0)),
+                Ray::new(Vec3::new(2.0 * x, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0)),
+                Ray::new(Vec3::new(x, -1.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+            ] {
+                let expected = list.hit(&ray, 0.0, f32::INFINITY).map(|hit| hit.t);
+                assert_eq!(expected, bvh.hit(&ray, 0.0, f32::INFINITY).map(|hit| hit.t));
+            }
+        }
+    }
+}
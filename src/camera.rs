@@ -1,4 +1,5 @@
 use std::f32::consts::PI;
+use crate::random;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 
@@ -11,6 +12,8 @@ pub struct Eye {
     pub u: Vec3,
     pub v: Vec3,
     pub lens_radius: f32,
+    pub shutter_open: f32,
+    pub shutter_close: f32,
 }
 
 impl Eye {
@@ -18,29 +21,43 @@ impl Eye {
         let rd = Vec3::random_in_unit_disk() * self.lens_radius;
         let offset = self.u * rd.x + self.v * rd.y;
 
-        Ray::new(
+        // Each ray samples a random moment while the shutter is open.
+        let time = if self.shutter_close > self.shutter_open {
+            random::random_range(self.shutter_open, self.shutter_close)
+        } else {
+            self.shutter_open
+        };
+
+        Ray::with_time(
             self.origin + offset,
             self.lower_left_corner + self.horizontal * s + self.vertical * t - self.origin - offset,
+            time,
         )
     }
 }
 
+// Everything that places and shapes a camera; angles are in degrees.
+pub struct CameraSettings {
+    pub lookfrom: Vec3,
+    pub lookat: Vec3,
+    pub vup: Vec3,
+    pub vfov: f32,
+    pub aspect: f32,
+    pub ipd: f32,
+    pub defocus_angle: f32,
+    pub focus_dist: f32,
+    // Open and close times.
+    pub shutter: (f32, f32),
+}
+
 pub struct Camera {
     pub left_eye: Eye,
     pub right_eye: Eye,
 }
 
 impl Camera {
-    pub fn new(
-        lookfrom: Vec3,
-        lookat: Vec3,
-        vup: Vec3,
-        vfov: f32,
-        aspect: f32,
-        ipd: f32,
-        defocus_angle: f32,
-        focus_dist: f32
-    ) -> Camera {
+    pub fn new(settings: &CameraSettings) -> Camera {
+        let CameraSettings { lookfrom, lookat, vup, vfov, aspect, ipd, defocus_angle, focus_dist, shutter } = *settings;
         let theta = vfov * PI / 180.0;
         let half_height = (theta / 2.0).tan();
         let half_width = aspect * half_height;
@@ -62,6 +79,8 @@ impl Camera {
             u,
             v,
             lens_radius,
+            shutter_open: shutter.0,
+            shutter_close: shutter.1,
         };
 
         let right_origin = lookfrom + u * half_ipd;
@@ -73,6 +92,8 @@ impl Camera {
             u,
             v,
             lens_radius,
+            shutter_open: shutter.0,
+            shutter_close: shutter.1,
         };
 
         Camera {
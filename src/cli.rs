@@ -0,0 +1,164 @@
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use crate::image::ImageFormat;
+use crate::progress::ProgressFormat;
+use crate::scheduler::TileOrder;
+
+pub const USAGE: &str = "\
+Usage: raytrace [OPTIONS] [SCENE]
+
+Arguments:
+  [SCENE]  A .rt, .toml, .gltf or .glb scene file, or a built-in scene: random, simple,
+           cornell [default: random]
+
+Options:
+  -o, --output <PATH>    Output image path, or - for stdout [default: out.ppm]
+  -f, --format <FORMAT>  Output format: ppm, ppm-ascii, png, png16, exr, exr32, hdr
+                         [default: from the output extension]
+  -W, --width <N>        Image width in pixels [default: 3840]
+  -H, --height <N>       Image height in pixels [default: 2160]
+  -s, --samples <N>      Samples per pixel [default: 1000]
+  -d, --max-depth <N>    Maximum number of bounces per path [default: 50]
+  -j, --threads <N>      Number of render threads [default: available cores]
+  -t, --tile-size <N>    Edge length of the square tiles handed to threads [default: 32]
+      --tile-order <ORDER>
+                         Order tiles are rendered in: scanline, spiral, hilbert [default: spiral]
+  -p, --progress <MODE>  Progress report on stderr: human, json (one object per line), off
+                         [default: human]
+  -a, --anaglyph         Render a red/cyan anaglyph image
+      --seed <N>         Seed the random number generator for reproducible renders
+  -h, --help             Print this help
+";
+
+#[derive(Debug)]
+pub struct CliError(String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for CliError {}
+
+pub struct Args {
+    pub scene: Option<String>,
+    pub output: PathBuf,
+    pub format: Option<ImageFormat>,
+    pub width: i32,
+    pub height: i32,
+    pub samples: i32,
+    pub max_depth: i32,
+    pub threads: Option<usize>,
+    pub tile_size: usize,
+    pub tile_order: TileOrder,
+    pub progress: ProgressFormat,
+    pub anaglyph: bool,
+    pub seed: Option<u64>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            scene: None,
+            output: PathBuf::from("out.ppm"),
+            format: None,
+            width: 3840,
+            height: 2160,
+            samples: 1000,
+            max_depth: 50,
+            threads: None,
+            tile_size: 32,
+            tile_order: TileOrder::Spiral,
+            progress: ProgressFormat::Human,
+            anaglyph: false,
+            seed: None,
+        }
+    }
+}
+
+pub enum Command {
+    Render(Args),
+    Help,
+}
+
+fn value<I: Iterator<Item = String>>(name: &str, inline: Option<String>, args: &mut I) -> Result<String, CliError> {
+    match inline.or_else(|| args.next()) {
+        Some(value) => Ok(value),
+        None => Err(CliError(format!("option '{}' requires a value", name))),
+    }
+}
+
+fn number<T: FromStr>(name: &str, value: &str) -> Result<T, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError(format!("invalid value '{}' for '{}'", value, name)))
+}
+
+fn positive(name: &str, value: &str) -> Result<i32, CliError> {
+    match number::<i32>(name, value)? {
+        n if n > 0 => Ok(n),
+        _ => Err(CliError(format!("'{}' must be greater than zero, got {}", name, value))),
+    }
+}
+
+pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Command, CliError> {
+    let mut parsed = Args::default();
+
+    while let Some(arg) = args.next() {
+        // Accept both "--name value" and "--name=value".
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+
+        match name.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-o" | "--output" => parsed.output = PathBuf::from(value(&name, inline, &mut args)?),
+            "-f" | "--format" => {
+                let format = value(&name, inline, &mut args)?;
+                match ImageFormat::from_name(&format) {
+                    Some(format) => parsed.format = Some(format),
+                    None => return Err(CliError(format!("unknown output format '{}'", format))),
+                }
+            }
+            "-W" | "--width" => parsed.width = positive(&name, &value(&name, inline, &mut args)?)?,
+            "-H" | "--height" => parsed.height = positive(&name, &value(&name, inline, &mut args)?)?,
+            "-s" | "--samples" => parsed.samples = positive(&name, &value(&name, inline, &mut args)?)?,
+            "-d" | "--max-depth" => parsed.max_depth = positive(&name, &value(&name, inline, &mut args)?)?,
+            "-j" | "--threads" => {
+                parsed.threads = Some(positive(&name, &value(&name, inline, &mut args)?)? as usize)
+            }
+            "-t" | "--tile-size" => parsed.tile_size = positive(&name, &value(&name, inline, &mut args)?)? as usize,
+            "--tile-order" => {
+                let order = value(&name, inline, &mut args)?;
+                match TileOrder::from_name(&order) {
+                    Some(order) => parsed.tile_order = order,
+                    None => return Err(CliError(format!("unknown tile order '{}'", order))),
+                }
+            }
+            "-p" | "--progress" => {
+                let mode = value(&name, inline, &mut args)?;
+                match ProgressFormat::from_name(&mode) {
+                    Some(mode) => parsed.progress = mode,
+                    None => return Err(CliError(format!("unknown progress mode '{}'", mode))),
+                }
+            }
+            "-a" | "--anaglyph" => parsed.anaglyph = true,
+            "--seed" => parsed.seed = Some(number(&name, &value(&name, inline, &mut args)?)?),
+            _ if name.starts_with('-') && name.len() > 1 => {
+                return Err(CliError(format!("unknown option '{}'", name)));
+            }
+            _ => {
+                if let Some(scene) = &parsed.scene {
+                    return Err(CliError(format!("unexpected argument '{}' (scene already set to '{}')", arg, scene)));
+                }
+                parsed.scene = Some(arg);
+            }
+        }
+    }
+
+    Ok(Command::Render(parsed))
+}
@@ -1,25 +1,41 @@
+use std::cell::Cell;
 use crate::hits::Hittable;
 use crate::hits::HittableList;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 
-pub fn color(ray: &Ray, world: &HittableList, depth: i32) -> Vec3 {
+thread_local! {
+    static RAYS_TRACED: Cell<u64> = const { Cell::new(0) };
+}
+
+// Number of rays traced so far by the calling thread.
+pub fn rays_traced() -> u64 {
+    RAYS_TRACED.with(|rays| rays.get())
+}
+
+pub fn color(ray: &Ray, world: &HittableList, depth: i32, background: Option<Vec3>) -> Vec3 {
     if depth <= 0 {
         return Vec3::new(0.0, 0.0, 0.0);
     }
 
+    RAYS_TRACED.with(|rays| rays.set(rays.get() + 1));
+
     match world.hit(ray, 0.001, f32::MAX) {
         Some(hit) => {
+            let emitted = hit.material.emitted();
             if let Some((attenuation, scattered)) = hit.material.scatter(ray, &hit) {
-                attenuation * color(&scattered, world, depth - 1)
+                emitted + attenuation * color(&scattered, world, depth - 1, background)
             } else {
-                Vec3::new(0.0, 0.0, 0.0)
+                emitted
             }
         },
-        None => {
-            let unit_dir = ray.direction().unit_vector();
-            let t = 0.5 * (unit_dir.y + 1.0);
-            (Vec3::new(1.0, 1.0, 1.0) * (1.0 - t)) + (Vec3::new(0.5, 0.7, 1.0) * t)
+        None => match background {
+            Some(background) => background,
+            None => {
+                let unit_dir = ray.direction().unit_vector();
+                let t = 0.5 * (unit_dir.y + 1.0);
+                (Vec3::new(1.0, 1.0, 1.0) * (1.0 - t)) + (Vec3::new(0.5, 0.7, 1.0) * t)
+            }
         }
     }
 }
@@ -0,0 +1,116 @@
+use crate::bounds::BBox;
+use crate::disk::Annulus;
+use crate::hits::{surface_spans, HitRecord, Hittable, Span};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// A cone with its tip at `apex`, opening along `axis` to a base of `radius` at `height`.
+// Open cones have no base cap and can be seen into.
+pub struct Cone {
+    pub apex: Vec3,
+    pub axis: Vec3,
+    pub radius: f32,
+    pub height: f32,
+    pub closed: bool,
+    pub material: Box<dyn Material>,
+    base: Annulus,
+}
+
+impl Cone {
+    pub fn new(apex: Vec3, axis: Vec3, radius: f32, height: f32, closed: bool, material: Box<dyn Material>) -> Cone {
+        let axis = axis.unit_vector();
+        let base = Annulus::new(apex + axis * height, axis, radius, 0.0);
+        Cone { apex, axis, radius, height, closed, material, base }
+    }
+
+    // `angle` is the half-angle at the apex, in degrees.
+    pub fn with_angle(apex: Vec3, axis: Vec3, angle: f32, height: f32, closed: bool, material: Box<dyn Material>) -> Cone {
+        Cone::new(apex, axis, height * angle.to_radians().tan(), height, closed, material)
+    }
+
+    fn hit_surface(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        // Points w = p - apex on the infinite double cone satisfy |w|^2 = (1 + k^2) (w.axis)^2.
+        let k = self.radius / self.height;
+        let m = 1.0 + k * k;
+        let oc = ray.origin() - self.apex;
+        let dv = ray.direction().dot(self.axis);
+        let ov = oc.dot(self.axis);
+
+        let a = ray.direction().dot(ray.direction()) - m * dv * dv;
+        let b = ray.direction().dot(oc) - m * dv * ov;
+        let c = oc.dot(oc) - m * ov * ov;
+
+        let roots = if a.abs() < 1e-8 {
+            // The ray runs parallel to the slant and crosses the surface at most once.
+            if b.abs() < 1e-8 {
+                return None;
+            }
+            let t = -c / (2.0 * b);
+            [t, t]
+        } else {
+            let discriminant = b * b - a * c;
+            if discriminant <= 0.0 {
+                return None;
+            }
+            let sqrt_discriminant = discriminant.sqrt();
+            let (t0, t1) = ((-b - sqrt_discriminant) / a, (-b + sqrt_discriminant) / a);
+            if t0 < t1 { [t0, t1] } else { [t1, t0] }
+        };
+
+        for t in roots {
+            if t < t_max && t > t_min {
+                let p = ray.at(t);
+                let w = p - self.apex;
+                let along = w.dot(self.axis);
+                // Reject the mirrored nappe behind the apex and anything past the base.
+                if along >= 0.0 && along <= self.height {
+                    let normal = (w - self.axis * (m * along)).unit_vector();
+                    return Some(HitRecord {
+                        t,
+                        p,
+                        normal,
+                        u: 0.0,
+                        v: 0.0,
+                        uv: None,
+                        color: None,
+                        material: self.material.as_ref(),
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let hit_anything = self.hit_surface(ray, t_min, t_max);
+        if !self.closed {
+            return hit_anything;
+        }
+
+        let closest_so_far = hit_anything.as_ref().map_or(t_max, |hit| hit.t);
+        match self.base.intersect(ray, t_min, closest_so_far) {
+            Some(t) => Some(HitRecord {
+                t,
+                p: ray.at(t),
+                normal: self.axis,
+                u: 0.0,
+                v: 0.0,
+                uv: None,
+                color: None,
+                material: self.material.as_ref(),
+            }),
+            None => hit_anything,
+        }
+    }
+
+    fn bounding_box(&self) -> BBox {
+        BBox::surrounding(&self.base.bounding_box(), &BBox::from_points(self.apex, self.apex))
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        if self.closed { surface_spans(self, ray) } else { Vec::new() }
+    }
+}
@@ -0,0 +1,172 @@
+use crate::bounds::BBox;
+use crate::hits::{HitRecord, Hittable, Span};
+use crate::ray::Ray;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Operation {
+    Union,
+    Intersection,
+    // The left object with the right one carved out of it.
+    Difference,
+}
+
+impl Operation {
+    fn inside(self, left: bool, right: bool) -> bool {
+        match self {
+            Operation::Union => left || right,
+            Operation::Intersection => left && right,
+            Operation::Difference => left && !right,
+        }
+    }
+}
+
+// A boolean combination of two closed objects. Each ray is split into the spans it spends
+// inside either operand, and the combined surface is wherever the operation's answer flips.
+pub struct Csg {
+    pub operation: Operation,
+    pub left: Box<dyn Hittable>,
+    pub right: Box<dyn Hittable>,
+}
+
+impl Csg {
+    pub fn new(operation: Operation, left: Box<dyn Hittable>, right: Box<dyn Hittable>) -> Csg {
+        Csg { operation, left, right }
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.spans(ray)
+            .into_iter()
+            .flat_map(|span| [span.enter, span.exit])
+            .find(|rec| rec.t > t_min && rec.t < t_max)
+    }
+
+    fn bounding_box(&self) -> BBox {
+        match self.operation {
+            Operation::Union => BBox::surrounding(&self.left.bounding_box(), &self.right.bounding_box()),
+            Operation::Intersection => BBox::overlap(&self.left.bounding_box(), &self.right.bounding_box()),
+            Operation::Difference => self.left.bounding_box(),
+        }
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        let left = self.left.spans(ray);
+        if left.is_empty() && self.operation != Operation::Union {
+            return Vec::new();
+        }
+        let right = self.right.spans(ray);
+
+        // Every boundary of either operand, in order along the ray: (record, from the right, entering).
+        let mut events = Vec::with_capacity(2 * (left.len() + right.len()));
+        for (spans, from_right) in [(&left, false), (&right, true)] {
+            for span in spans {
+                events.push((span.enter, from_right, true));
+                events.push((span.exit, from_right, false));
+            }
+        }
+        events.sort_by(|a, b| a.0.t.total_cmp(&b.0.t));
+
+        let mut result = Vec::new();
+        let mut enter = None;
+        let (mut in_left, mut in_right) = (false, false);
+        for (mut rec, from_right, entering) in events {
+            let was_inside = self.operation.inside(in_left, in_right);
+            if from_right { in_right = entering } else { in_left = entering }
+            let is_inside = self.operation.inside(in_left, in_right);
+            if was_inside == is_inside {
+                continue;
+            }
+
+            // The carved-out object's surface faces into it, out of what is left.
+            if from_right && self.operation == Operation::Difference {
+                rec.normal = -rec.normal;
+            }
+            if is_inside {
+                enter = Some(rec);
+            } else if let Some(enter) = enter.take() {
+                result.push(Span { enter, exit: rec });
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cylinder::Cylinder;
+    use crate::material::Lambertian;
+    use crate::sphere::Sphere;
+    use crate::vec3::Vec3;
+
+    // A unit sphere and a thinner, longer cylinder through it along z.
+    fn sphere_and_cylinder(operation: Operation) -> Csg {
+        let material = || Box::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        let sphere = Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0, material());
+        let cylinder = Cylinder::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 0.5, 4.0, material());
+        Csg::new(operation, Box::new(sphere), Box::new(cylinder))
+    }
+
+    // Each span's entry and exit distance and the x component of their normals.
+    fn spans(csg: &Csg, origin: Vec3, direction: Vec3) -> Vec<[f32; 4]> {
+        let ray = Ray::new(origin, direction);
+        csg.spans(&ray)
+            .iter()
+            .map(|span| [span.enter.t, span.exit.t, span.enter.normal.x, span.exit.normal.x])
+            .collect()
+    }
+
+    fn assert_spans(found: Vec<[f32; 4]>, expected: &[[f32; 4]]) {
+        assert_eq!(found.len(), expected.len(), "{:?}", found);
+        for (span, e) in found.iter().zip(expected) {
+            for (value, e) in span.iter().zip(e) {
+                assert!((value - e).abs() < 1e-4, "{:?} != {:?}", found, expected);
+            }
+        }
+    }
+
+    const ALONG_X: Vec3 = Vec3 { x: 1.0, y: 0.0, z: 0.0 };
+
+    #[test]
+    fn union_spans_both_operands() {
+        let csg = sphere_and_cylinder(Operation::Union);
+        assert_spans(spans(&csg, Vec3::new(-5.0, 0.0, 0.0), ALONG_X), &[[4.0, 6.0, -1.0, 1.0]]);
+        // Only the cylinder reaches this far along z.
+        assert_spans(spans(&csg, Vec3::new(-5.0, 0.0, 1.5), ALONG_X), &[[4.5, 5.5, -1.0, 1.0]]);
+    }
+
+    #[test]
+    fn intersection_keeps_the_overlap() {
+        let csg = sphere_and_cylinder(Operation::Intersection);
+        assert_spans(spans(&csg, Vec3::new(-5.0, 0.0, 0.0), ALONG_X), &[[4.5, 5.5, -1.0, 1.0]]);
+        assert!(spans(&csg, Vec3::new(-5.0, 0.0, 1.5), ALONG_X).is_empty());
+    }
+
+    #[test]
+    fn difference_flips_the_carved_surface() {
+        let csg = sphere_and_cylinder(Operation::Difference);
+        // The hole's walls face into it, out of what is left of the sphere.
+        let found = spans(&csg, Vec3::new(-5.0, 0.0, 0.0), ALONG_X);
+        assert_spans(found, &[[4.0, 4.5, -1.0, 1.0], [5.5, 6.0, -1.0, 1.0]]);
+        // Straight down the hole nothing is left.
+        assert!(spans(&csg, Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0)).is_empty());
+    }
+
+    #[test]
+    fn rays_starting_inside_hit_the_way_out() {
+        let hit = |operation, origin| {
+            let csg = sphere_and_cylinder(operation);
+            let rec = csg.hit(&Ray::new(origin, ALONG_X), 0.001, f32::INFINITY).unwrap();
+            (rec.t, rec.normal.x)
+        };
+        let close = |(t, x): (f32, f32), (et, ex): (f32, f32)| (t - et).abs() < 1e-4 && (x - ex).abs() < 1e-4;
+
+        assert!(close(hit(Operation::Union, Vec3::new(0.0, 0.0, 1.5)), (0.5, 1.0)));
+        assert!(close(hit(Operation::Intersection, Vec3::new(0.0, 0.0, 0.0)), (0.5, 1.0)));
+        // From inside the sphere's wall, the first surface is the hole.
+        assert!(close(hit(Operation::Difference, Vec3::new(-0.75, 0.0, 0.0)), (0.25, 1.0)));
+        // From inside the hole, the next surface is the far wall, entered.
+        assert!(close(hit(Operation::Difference, Vec3::new(0.0, 0.0, 0.0)), (0.5, -1.0)));
+    }
+}
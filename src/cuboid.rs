@@ -0,0 +1,69 @@
+use crate::bounds::BBox;
+use crate::hits::{surface_spans, HitRecord, Hittable, Span};
+use crate::material::Material;
+use crate::quad::Parallelogram;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// An axis-aligned box made of six parallelograms, all with outward normals.
+pub struct Cuboid {
+    pub sides: [Parallelogram; 6],
+    pub material: Box<dyn Material>,
+}
+
+impl Cuboid {
+    // `a` and `b` are any two opposite corners.
+    pub fn new(a: Vec3, b: Vec3, material: Box<dyn Material>) -> Cuboid {
+        let min = Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+        let max = Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+        let dx = Vec3::new(max.x - min.x, 0.0, 0.0);
+        let dy = Vec3::new(0.0, max.y - min.y, 0.0);
+        let dz = Vec3::new(0.0, 0.0, max.z - min.z);
+
+        let sides = [
+            Parallelogram::new(Vec3::new(min.x, min.y, max.z), dx, dy), // front
+            Parallelogram::new(Vec3::new(max.x, min.y, max.z), -dz, dy), // right
+            Parallelogram::new(Vec3::new(max.x, min.y, min.z), -dx, dy), // back
+            Parallelogram::new(Vec3::new(min.x, min.y, min.z), dz, dy), // left
+            Parallelogram::new(Vec3::new(min.x, max.y, max.z), dx, -dz), // top
+            Parallelogram::new(Vec3::new(min.x, min.y, min.z), dx, dz), // bottom
+        ];
+        Cuboid { sides, material }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let mut hit_anything = None;
+        let mut closest_so_far = t_max;
+
+        for side in &self.sides {
+            if let Some((t, u, v)) = side.intersect(ray, t_min, closest_so_far) {
+                closest_so_far = t;
+                hit_anything = Some(HitRecord {
+                    t,
+                    p: ray.at(t),
+                    normal: side.normal,
+                    u,
+                    v,
+                    uv: None,
+                    color: None,
+                    material: self.material.as_ref(),
+                });
+            }
+        }
+
+        hit_anything
+    }
+
+    fn bounding_box(&self) -> BBox {
+        self.sides
+            .iter()
+            .fold(BBox::default(), |bbox, side| BBox::surrounding(&bbox, &side.bounding_box()))
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        surface_spans(self, ray)
+    }
+}
@@ -0,0 +1,100 @@
+use crate::bounds::BBox;
+use crate::disk::Annulus;
+use crate::hits::{surface_spans, HitRecord, Hittable, Span};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// A capped cylinder centered on `center`, extending half its height along each direction of `axis`.
+pub struct Cylinder {
+    pub center: Vec3,
+    pub axis: Vec3,
+    pub radius: f32,
+    pub height: f32,
+    pub material: Box<dyn Material>,
+    caps: [Annulus; 2],
+}
+
+impl Cylinder {
+    pub fn new(center: Vec3, axis: Vec3, radius: f32, height: f32, material: Box<dyn Material>) -> Cylinder {
+        let axis = axis.unit_vector();
+        let half = axis * (height / 2.0);
+        let caps = [
+            Annulus::new(center + half, axis, radius, 0.0),
+            Annulus::new(center - half, -axis, radius, 0.0),
+        ];
+        Cylinder { center, axis, radius, height, material, caps }
+    }
+
+    fn hit_body(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        // Project the ray onto the plane perpendicular to the axis, where the body is a circle.
+        let oc = ray.origin() - self.center;
+        let d = ray.direction() - self.axis * ray.direction().dot(self.axis);
+        let o = oc - self.axis * oc.dot(self.axis);
+
+        let a = d.dot(d);
+        if a < 1e-12 {
+            return None;
+        }
+        let b = o.dot(d);
+        let c = o.dot(o) - self.radius * self.radius;
+        let discriminant = b * b - a * c;
+        if discriminant <= 0.0 {
+            return None;
+        }
+
+        let sqrt_discriminant = discriminant.sqrt();
+        for t in [(-b - sqrt_discriminant) / a, (-b + sqrt_discriminant) / a] {
+            if t < t_max && t > t_min {
+                let p = ray.at(t);
+                let m = (p - self.center).dot(self.axis);
+                if m.abs() <= self.height / 2.0 {
+                    let normal = (p - self.center - self.axis * m) / self.radius;
+                    return Some(HitRecord {
+                        t,
+                        p,
+                        normal,
+                        u: 0.0,
+                        v: 0.0,
+                        uv: None,
+                        color: None,
+                        material: self.material.as_ref(),
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let mut hit_anything = self.hit_body(ray, t_min, t_max);
+
+        for cap in &self.caps {
+            let closest_so_far = hit_anything.as_ref().map_or(t_max, |hit| hit.t);
+            if let Some(t) = cap.intersect(ray, t_min, closest_so_far) {
+                hit_anything = Some(HitRecord {
+                    t,
+                    p: ray.at(t),
+                    normal: cap.normal,
+                    u: 0.0,
+                    v: 0.0,
+                    uv: None,
+                    color: None,
+                    material: self.material.as_ref(),
+                });
+            }
+        }
+
+        hit_anything
+    }
+
+    fn bounding_box(&self) -> BBox {
+        BBox::surrounding(&self.caps[0].bounding_box(), &self.caps[1].bounding_box())
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        surface_spans(self, ray)
+    }
+}
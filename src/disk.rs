@@ -0,0 +1,79 @@
+use crate::bounds::BBox;
+use crate::hits::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// A flat ring around `center` between `inner_radius` and `radius`; a full disk when the
+// inner radius is zero. Shared by `Disk` and the caps of cylinders and cones.
+#[derive(Copy, Clone)]
+pub struct Annulus {
+    pub center: Vec3,
+    pub normal: Vec3,
+    pub radius: f32,
+    pub inner_radius: f32,
+}
+
+impl Annulus {
+    pub fn new(center: Vec3, normal: Vec3, radius: f32, inner_radius: f32) -> Annulus {
+        Annulus { center, normal: normal.unit_vector(), radius, inner_radius }
+    }
+
+    pub fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+        let denom = self.normal.dot(ray.direction());
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.center - ray.origin()).dot(self.normal) / denom;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let distance = (ray.at(t) - self.center).sqr_length();
+        if distance > self.radius * self.radius || distance < self.inner_radius * self.inner_radius {
+            return None;
+        }
+        Some(t)
+    }
+
+    pub fn bounding_box(&self) -> BBox {
+        // The extent along a world axis shrinks as the normal lines up with it.
+        let mut extent = Vec3::new(0.0, 0.0, 0.0);
+        for i in 0..3 {
+            extent[i] = self.radius * (1.0 - self.normal[i] * self.normal[i]).max(0.0).sqrt();
+        }
+        BBox::from_points(self.center - extent, self.center + extent).pad()
+    }
+}
+
+pub struct Disk {
+    pub shape: Annulus,
+    pub material: Box<dyn Material>,
+}
+
+impl Disk {
+    pub fn new(center: Vec3, normal: Vec3, radius: f32, inner_radius: f32, material: Box<dyn Material>) -> Disk {
+        Disk { shape: Annulus::new(center, normal, radius, inner_radius), material }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let t = self.shape.intersect(ray, t_min, t_max)?;
+        Some(HitRecord {
+            t,
+            p: ray.at(t),
+            normal: self.shape.normal,
+            u: 0.0,
+            v: 0.0,
+            uv: None,
+            color: None,
+            material: self.material.as_ref(),
+        })
+    }
+
+    fn bounding_box(&self) -> BBox {
+        self.shape.bounding_box()
+    }
+}
@@ -0,0 +1,218 @@
+use std::io::{self, Write};
+
+const MAGIC: u32 = 20000630;
+const VERSION: u32 = 2; // single-part scanline file, no feature flags
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PixelType {
+    Half,
+    Float,
+}
+
+impl PixelType {
+    fn id(self) -> i32 {
+        match self {
+            PixelType::Half => 1,
+            PixelType::Float => 2,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            PixelType::Half => 2,
+            PixelType::Float => 4,
+        }
+    }
+}
+
+// Converts to IEEE 754 binary16, rounding to nearest even.
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x007F_FFFF;
+
+    if exponent == 0xFF {
+        // Infinity stays infinity, NaN stays a (quiet) NaN.
+        return sign | 0x7C00 | if mantissa != 0 { 0x0200 } else { 0 };
+    }
+
+    let half_exponent = exponent - 127 + 15;
+    if half_exponent >= 0x1F {
+        return sign | 0x7C00;
+    }
+
+    if half_exponent <= 0 {
+        if half_exponent < -10 {
+            return sign;
+        }
+        // Subnormal half: shift the mantissa (with its implicit leading one) into place.
+        let m = mantissa | 0x0080_0000;
+        let shift = (14 - half_exponent) as u32;
+        let half_m = m >> shift;
+        let remainder = m & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        let round = remainder > halfway || (remainder == halfway && (half_m & 1) != 0);
+        return sign | (half_m + round as u32) as u16;
+    }
+
+    let half = ((half_exponent as u32) << 10) | (mantissa >> 13);
+    let remainder = mantissa & 0x1FFF;
+    let round = remainder > 0x1000 || (remainder == 0x1000 && (half & 1) != 0);
+    // A carry out of the mantissa correctly bumps the exponent, up to infinity.
+    sign | (half + round as u32) as u16
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+fn box2i(width: usize, height: usize) -> Vec<u8> {
+    let mut value = Vec::with_capacity(16);
+    for v in [0, 0, width as i32 - 1, height as i32 - 1] {
+        value.extend_from_slice(&v.to_le_bytes());
+    }
+    value
+}
+
+// Writes an uncompressed scanline OpenEXR image. Each channel is a (name, samples) pair
+// with `width * height` samples stored row by row from the top of the image.
+pub fn write_exr<W: Write>(
+    w: &mut W,
+    width: usize,
+    height: usize,
+    channels: &[(&str, &[f32])],
+    pixel_type: PixelType,
+) -> io::Result<()> {
+    // The channel list, and the sample layout within each scanline, must be sorted by name.
+    let mut channels = channels.to_vec();
+    channels.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut chlist = Vec::new();
+    for (name, _) in &channels {
+        chlist.extend_from_slice(name.as_bytes());
+        chlist.push(0);
+        chlist.extend_from_slice(&pixel_type.id().to_le_bytes());
+        chlist.extend_from_slice(&[0, 0, 0, 0]); // pLinear and reserved bytes
+        chlist.extend_from_slice(&1i32.to_le_bytes());
+        chlist.extend_from_slice(&1i32.to_le_bytes());
+    }
+    chlist.push(0);
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&MAGIC.to_le_bytes());
+    header.extend_from_slice(&VERSION.to_le_bytes());
+    attribute(&mut header, "channels", "chlist", &chlist);
+    attribute(&mut header, "compression", "compression", &[0]);
+    attribute(&mut header, "dataWindow", "box2i", &box2i(width, height));
+    attribute(&mut header, "displayWindow", "box2i", &box2i(width, height));
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(&mut header, "pixelAspectRatio", "float", &1.0f32.to_le_bytes());
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(&mut header, "screenWindowWidth", "float", &1.0f32.to_le_bytes());
+    header.push(0);
+
+    let line_size = width * channels.len() * pixel_type.size();
+    let first_block = header.len() + 8 * height;
+
+    w.write_all(&header)?;
+    for y in 0..height {
+        let offset = (first_block + y * (8 + line_size)) as u64;
+        w.write_all(&offset.to_le_bytes())?;
+    }
+
+    let mut line = Vec::with_capacity(line_size);
+    for y in 0..height {
+        line.clear();
+        for (_, samples) in &channels {
+            for &sample in &samples[y * width..(y + 1) * width] {
+                match pixel_type {
+                    PixelType::Half => line.extend_from_slice(&f32_to_f16(sample).to_le_bytes()),
+                    PixelType::Float => line.extend_from_slice(&sample.to_le_bytes()),
+                }
+            }
+        }
+        w.write_all(&(y as i32).to_le_bytes())?;
+        w.write_all(&(line_size as i32).to_le_bytes())?;
+        w.write_all(&line)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Exact f32 value of a finite binary16.
+    fn f16_to_f32(half: u16) -> f32 {
+        let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+        let exponent = ((half >> 10) & 0x1F) as i32;
+        let mantissa = (half & 0x03FF) as f32;
+        match exponent {
+            0 => sign * mantissa * 2f32.powi(-24),
+            _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+        }
+    }
+
+    #[test]
+    fn finite_halves_round_trip() {
+        for half in (0..=0xFFFFu16).filter(|h| h & 0x7C00 != 0x7C00) {
+            assert_eq!(f32_to_f16(f16_to_f32(half)), half, "{:#06x}", half);
+        }
+    }
+
+    #[test]
+    fn ties_round_to_even() {
+        let ulp = 2f32.powi(-10);
+        assert_eq!(f32_to_f16(1.0 + ulp / 2.0), 0x3C00);
+        assert_eq!(f32_to_f16(1.0 + 1.5 * ulp), 0x3C02);
+        assert_eq!(f32_to_f16(1.0 + ulp / 2.0 + 2f32.powi(-20)), 0x3C01);
+        assert_eq!(f32_to_f16(1.0 + 1.5 * ulp - 2f32.powi(-20)), 0x3C01);
+        // Rounding up a full mantissa carries into the exponent.
+        assert_eq!(f32_to_f16(2.0 - ulp / 4.0), 0x4000);
+    }
+
+    #[test]
+    fn subnormals() {
+        let smallest = 2f32.powi(-24);
+        assert_eq!(f32_to_f16(smallest), 0x0001);
+        assert_eq!(f32_to_f16(-smallest), 0x8001);
+        assert_eq!(f32_to_f16(2f32.powi(-15)), 0x0200);
+        assert_eq!(f32_to_f16(2f32.powi(-14)), 0x0400);
+        // Halfway cases below the smallest normal still round to even.
+        assert_eq!(f32_to_f16(smallest / 2.0), 0x0000);
+        assert_eq!(f32_to_f16(smallest * 1.5), 0x0002);
+        assert_eq!(f32_to_f16(smallest * 0.500_1), 0x0001);
+        assert_eq!(f32_to_f16(2f32.powi(-14) - smallest / 2.0), 0x0400);
+        assert_eq!(f32_to_f16(2f32.powi(-30)), 0x0000);
+        assert_eq!(f32_to_f16(-2f32.powi(-30)), 0x8000);
+        assert_eq!(f32_to_f16(f32::MIN_POSITIVE), 0x0000);
+    }
+
+    #[test]
+    fn overflow_becomes_infinity() {
+        assert_eq!(f32_to_f16(65504.0), 0x7BFF);
+        assert_eq!(f32_to_f16(65519.0), 0x7BFF);
+        assert_eq!(f32_to_f16(65520.0), 0x7C00);
+        assert_eq!(f32_to_f16(1e6), 0x7C00);
+        assert_eq!(f32_to_f16(f32::MAX), 0x7C00);
+        assert_eq!(f32_to_f16(-f32::MAX), 0xFC00);
+        assert_eq!(f32_to_f16(f32::INFINITY), 0x7C00);
+        assert_eq!(f32_to_f16(f32::NEG_INFINITY), 0xFC00);
+    }
+
+    #[test]
+    fn nan_stays_nan() {
+        for nan in [f32::NAN, -f32::NAN, f32::from_bits(0x7F80_0001)] {
+            let half = f32_to_f16(nan);
+            assert_eq!(half & 0x7C00, 0x7C00);
+            assert_ne!(half & 0x03FF, 0);
+        }
+    }
+}
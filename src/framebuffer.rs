@@ -0,0 +1,46 @@
+use std::io::{self, Write};
+use crate::image::{self, ImageFormat};
+use crate::vec3::Vec3;
+
+// Linear RGB radiance, stored row by row from the top of the image.
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vec3>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![Vec3::new(0.0, 0.0, 0.0); width * height],
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Vec3) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    pub fn write<W: Write>(&self, w: &mut W, format: ImageFormat) -> io::Result<()> {
+        image::write(w, format, self.width, self.height, &self.pixels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixels_are_written_row_by_row() {
+        let mut framebuffer = Framebuffer::new(2, 2);
+        framebuffer.set(1, 0, Vec3::new(1.0, 0.0, 0.0));
+        framebuffer.set(0, 1, Vec3::new(0.0, 0.0, 1.0));
+
+        let mut out = Vec::new();
+        framebuffer.write(&mut out, ImageFormat::Ppm).unwrap();
+        let header = b"P6\n2 2\n255\n";
+        assert_eq!(out[..header.len()], header[..]);
+        assert_eq!(out[header.len()..], [0, 0, 0, 255, 0, 0, 0, 0, 255, 0, 0, 0]);
+    }
+}
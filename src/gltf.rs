@@ -0,0 +1,867 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use crate::hits::HittableList;
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::mesh::{Face, Mesh};
+use crate::render::Render;
+use crate::scene::Scene;
+use crate::transform::{Mat4, Transform};
+use crate::vec3::Vec3;
+
+const GLB_MAGIC: u32 = 0x4654_6c67;
+const GLB_JSON: u32 = 0x4e4f_534a;
+const GLB_BIN: u32 = 0x004e_4942;
+const TRIANGLES: u32 = 4;
+// Accessors without a buffer view are all zeros and take no space in the file, so their
+// count is the only thing bounding the memory they need.
+const MAX_ZERO_ELEMENTS: usize = 1 << 24;
+
+#[derive(Debug)]
+pub enum GltfError {
+    Io(PathBuf, io::Error),
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GltfError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            GltfError::Invalid(path, message) => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl Error for GltfError {}
+
+// The parts of the glTF 2.0 schema the loader uses; anything else in the file is ignored.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Root {
+    #[serde(default)]
+    accessors: Vec<AccessorDef>,
+    #[serde(default)]
+    buffers: Vec<BufferDef>,
+    #[serde(default)]
+    buffer_views: Vec<BufferViewDef>,
+    #[serde(default)]
+    cameras: Vec<CameraDef>,
+    #[serde(default)]
+    materials: Vec<MaterialDef>,
+    #[serde(default)]
+    meshes: Vec<MeshDef>,
+    #[serde(default)]
+    nodes: Vec<NodeDef>,
+    scene: Option<usize>,
+    scenes: Option<Vec<SceneDef>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccessorDef {
+    buffer_view: Option<usize>,
+    #[serde(default)]
+    byte_offset: usize,
+    component_type: u32,
+    #[serde(default)]
+    normalized: bool,
+    count: usize,
+    #[serde(rename = "type")]
+    kind: String,
+    sparse: Option<IgnoredAny>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BufferDef {
+    uri: Option<String>,
+    byte_length: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BufferViewDef {
+    buffer: usize,
+    #[serde(default)]
+    byte_offset: usize,
+    byte_length: usize,
+    byte_stride: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct CameraDef {
+    perspective: Option<PerspectiveDef>,
+}
+
+#[derive(Deserialize)]
+struct PerspectiveDef {
+    yfov: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MaterialDef {
+    #[serde(default)]
+    pbr_metallic_roughness: PbrDef,
+    #[serde(default)]
+    emissive_factor: [f32; 3],
+    #[serde(default)]
+    extensions: MaterialExtensions,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PbrDef {
+    #[serde(default = "opaque_white")]
+    base_color_factor: [f32; 4],
+    #[serde(default = "one")]
+    metallic_factor: f32,
+    #[serde(default = "one")]
+    roughness_factor: f32,
+}
+
+impl Default for PbrDef {
+    fn default() -> Self {
+        PbrDef { base_color_factor: opaque_white(), metallic_factor: 1.0, roughness_factor: 1.0 }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct MaterialExtensions {
+    #[serde(rename = "KHR_materials_emissive_strength")]
+    emissive_strength: Option<EmissiveStrength>,
+    #[serde(rename = "KHR_materials_transmission")]
+    transmission: Option<Transmission>,
+    #[serde(rename = "KHR_materials_ior")]
+    ior: Option<Ior>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EmissiveStrength {
+    #[serde(default = "one")]
+    emissive_strength: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Transmission {
+    #[serde(default)]
+    transmission_factor: f32,
+}
+
+#[derive(Deserialize)]
+struct Ior {
+    #[serde(default = "default_ior")]
+    ior: f32,
+}
+
+#[derive(Deserialize)]
+struct MeshDef {
+    primitives: Vec<PrimitiveDef>,
+}
+
+#[derive(Deserialize)]
+struct PrimitiveDef {
+    attributes: HashMap<String, usize>,
+    indices: Option<usize>,
+    material: Option<usize>,
+    #[serde(default = "triangles")]
+    mode: u32,
+}
+
+#[derive(Deserialize)]
+struct NodeDef {
+    #[serde(default)]
+    children: Vec<usize>,
+    mesh: Option<usize>,
+    camera: Option<usize>,
+    matrix: Option<[f32; 16]>,
+    translation: Option<[f32; 3]>,
+    rotation: Option<[f32; 4]>,
+    scale: Option<[f32; 3]>,
+}
+
+#[derive(Deserialize)]
+struct SceneDef {
+    #[serde(default)]
+    nodes: Vec<usize>,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+fn opaque_white() -> [f32; 4] {
+    [1.0, 1.0, 1.0, 1.0]
+}
+
+fn default_ior() -> f32 {
+    1.5
+}
+
+fn triangles() -> u32 {
+    TRIANGLES
+}
+
+impl NodeDef {
+    fn matrix(&self) -> Mat4 {
+        if let Some(columns) = self.matrix {
+            return Mat4::from_columns(columns);
+        }
+        let [tx, ty, tz] = self.translation.unwrap_or([0.0, 0.0, 0.0]);
+        let [x, y, z, w] = self.rotation.unwrap_or([0.0, 0.0, 0.0, 1.0]);
+        let [sx, sy, sz] = self.scale.unwrap_or([1.0, 1.0, 1.0]);
+        Mat4::translation(Vec3::new(tx, ty, tz)) * Mat4::from_quaternion(x, y, z, w) * Mat4::scaling(Vec3::new(sx, sy, sz))
+    }
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let data = text.trim_end_matches('=');
+    if text.len() - data.len() > 2 || data.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in data.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        bits = bits << 6 | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+    Some(out)
+}
+
+// URIs of external files are percent-encoded relative paths.
+fn decode_uri(uri: &str) -> Option<String> {
+    let bytes = uri.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+struct Document {
+    path: PathBuf,
+    root: Root,
+    buffers: Vec<Vec<u8>>,
+}
+
+struct Camera {
+    position: Vec3,
+    forward: Vec3,
+    up: Vec3,
+    yfov: f32,
+}
+
+// An accessor's elements, checked to lie inside its buffer view.
+struct Elements<'a> {
+    // The whole buffer view; `None` when the accessor has none and reads as zeros.
+    view: Option<&'a [u8]>,
+    start: usize,
+    stride: usize,
+    count: usize,
+    components: usize,
+    component_type: u32,
+    normalized: bool,
+}
+
+impl Elements<'_> {
+    fn size(&self) -> usize {
+        match self.component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            _ => 4,
+        }
+    }
+
+    fn bytes(&self, element: usize, component: usize) -> Option<&[u8]> {
+        let offset = self.start + element * self.stride + component * self.size();
+        self.view.map(|view| &view[offset..offset + self.size()])
+    }
+
+    fn float(&self, element: usize, component: usize) -> f32 {
+        let Some(b) = self.bytes(element, component) else { return 0.0 };
+        let (raw, scale) = match self.component_type {
+            5120 => (b[0] as i8 as f32, 127.0),
+            5121 => (b[0] as f32, 255.0),
+            5122 => (i16::from_le_bytes([b[0], b[1]]) as f32, 32767.0),
+            5123 => (u16::from_le_bytes([b[0], b[1]]) as f32, 65535.0),
+            5125 => (u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32, 1.0),
+            _ => (f32::from_le_bytes([b[0], b[1], b[2], b[3]]), 1.0),
+        };
+        if self.normalized { (raw / scale).max(-1.0) } else { raw }
+    }
+
+    fn integer(&self, element: usize) -> u32 {
+        match self.bytes(element, 0) {
+            None => 0,
+            Some([b]) => *b as u32,
+            Some([b0, b1]) => u16::from_le_bytes([*b0, *b1]) as u32,
+            Some(b) => u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        }
+    }
+}
+
+// Reads a .gltf (with external or embedded buffers) or a binary .glb file.
+fn read_document(path: &Path) -> Result<Document, GltfError> {
+    let bytes = fs::read(path).map_err(|e| GltfError::Io(path.to_path_buf(), e))?;
+    parse_document(path, &bytes)
+}
+
+// External buffers are resolved against the directory of `path`.
+fn parse_document(path: &Path, bytes: &[u8]) -> Result<Document, GltfError> {
+    let invalid = |message: String| GltfError::Invalid(path.to_path_buf(), message);
+    let word = |offset: usize| -> Option<u32> {
+        let b = bytes.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    let (json, mut bin) = if word(0) == Some(GLB_MAGIC) {
+        if word(4) != Some(2) {
+            return Err(invalid("only glTF 2.0 binary files are supported".to_string()));
+        }
+        let mut chunks = Vec::new();
+        let mut offset = 12;
+        while let (Some(length), Some(kind)) = (word(offset), word(offset + 4)) {
+            let data = bytes
+                .get(offset + 8..offset + 8 + length as usize)
+                .ok_or_else(|| invalid("truncated GLB chunk".to_string()))?;
+            chunks.push((kind, data));
+            offset += 8 + length as usize;
+        }
+        let json = match chunks.first() {
+            Some((GLB_JSON, data)) => *data,
+            _ => return Err(invalid("GLB file does not start with a JSON chunk".to_string())),
+        };
+        let bin = chunks.iter().find(|(kind, _)| *kind == GLB_BIN).map(|(_, data)| data.to_vec());
+        (json, bin)
+    } else {
+        (bytes, None)
+    };
+
+    let root: Root = serde_json::from_slice(json).map_err(|e| invalid(e.to_string()))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut buffers = Vec::new();
+
+    for (index, buffer) in root.buffers.iter().enumerate() {
+        let data = match &buffer.uri {
+            Some(uri) if uri.starts_with("data:") => {
+                // data:[<media type>][;base64],<data>; only base64 suits binary buffers.
+                let encoded = match uri.split_once(',') {
+                    Some((header, data)) if header.ends_with(";base64") => data,
+                    _ => return Err(invalid(format!("buffers[{}]: only base64 data URIs are supported", index))),
+                };
+                decode_base64(encoded).ok_or_else(|| invalid(format!("buffers[{}]: invalid base64 data", index)))?
+            }
+            Some(uri) => {
+                let name = decode_uri(uri).ok_or_else(|| invalid(format!("buffers[{}]: invalid URI '{}'", index, uri)))?;
+                let file = dir.join(name);
+                fs::read(&file).map_err(|e| GltfError::Io(file, e))?
+            }
+            // A buffer without a URI refers to the GLB binary chunk.
+            None => bin.take().ok_or_else(|| invalid(format!("buffers[{}]: missing data", index)))?,
+        };
+        if data.len() < buffer.byte_length {
+            return Err(invalid(format!(
+                "buffers[{}]: expected {} bytes, found {}",
+                index,
+                buffer.byte_length,
+                data.len()
+            )));
+        }
+        buffers.push(data);
+    }
+
+    Ok(Document { path: path.to_path_buf(), root, buffers })
+}
+
+fn item<'a, T>(document: &Document, items: &'a [T], kind: &str, index: usize) -> Result<&'a T, GltfError> {
+    items.get(index).ok_or_else(|| document.invalid(format!("{}[{}] does not exist", kind, index)))
+}
+
+impl Document {
+    fn invalid(&self, message: String) -> GltfError {
+        GltfError::Invalid(self.path.clone(), message)
+    }
+
+    // Locates an accessor's data, making sure every element lies inside its buffer view
+    // before anything is read or allocated.
+    fn elements(&self, index: usize) -> Result<Elements<'_>, GltfError> {
+        let accessor = item(self, &self.root.accessors, "accessors", index)?;
+        let error = |message: &str| self.invalid(format!("accessors[{}]: {}", index, message));
+
+        if accessor.sparse.is_some() {
+            return Err(error("sparse accessors are not supported"));
+        }
+        let components = match accessor.kind.as_str() {
+            "SCALAR" => 1,
+            "VEC2" => 2,
+            "VEC3" => 3,
+            "VEC4" => 4,
+            _ => return Err(error("unsupported type")),
+        };
+        let size = match accessor.component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => return Err(error("unsupported componentType")),
+        };
+        let element_size = size * components;
+        let mut elements = Elements {
+            view: None,
+            start: accessor.byte_offset,
+            stride: element_size,
+            count: accessor.count,
+            components,
+            component_type: accessor.component_type,
+            normalized: accessor.normalized,
+        };
+
+        let Some(view_index) = accessor.buffer_view else {
+            if accessor.count > MAX_ZERO_ELEMENTS {
+                return Err(error("count is too large for an accessor without a buffer view"));
+            }
+            return Ok(elements);
+        };
+        let view = item(self, &self.root.buffer_views, "bufferViews", view_index)?;
+        let buffer = self.buffers.get(view.buffer).ok_or_else(|| error("buffer view refers to a missing buffer"))?;
+        let data = view
+            .byte_offset
+            .checked_add(view.byte_length)
+            .and_then(|end| buffer.get(view.byte_offset..end))
+            .ok_or_else(|| self.invalid(format!("bufferViews[{}]: runs past the end of its buffer", view_index)))?;
+
+        if let Some(stride) = view.byte_stride {
+            if stride < element_size {
+                return Err(error("byteStride is smaller than an element"));
+            }
+            elements.stride = stride;
+        }
+        // The last element has to end inside the view; the others lie before it.
+        if let Some(last) = accessor.count.checked_sub(1) {
+            let end = last
+                .checked_mul(elements.stride)
+                .and_then(|offset| offset.checked_add(accessor.byte_offset))
+                .and_then(|offset| offset.checked_add(element_size));
+            if end.is_none_or(|end| end > data.len()) {
+                return Err(error("data runs past the end of its buffer view"));
+            }
+        }
+        elements.view = Some(data);
+        Ok(elements)
+    }
+
+    // Reads an accessor as `count` rows of float components, normalizing integer data when flagged.
+    fn accessor(&self, index: usize) -> Result<(Vec<f32>, usize), GltfError> {
+        let elements = self.elements(index)?;
+        let mut values = Vec::with_capacity(elements.count * elements.components);
+        for element in 0..elements.count {
+            for component in 0..elements.components {
+                values.push(elements.float(element, component));
+            }
+        }
+        Ok((values, elements.components))
+    }
+
+    // Reads an index accessor exactly; going through floats would round indices above 2^24.
+    fn indices(&self, index: usize) -> Result<Vec<usize>, GltfError> {
+        let elements = self.elements(index)?;
+        if elements.components != 1 || !matches!(elements.component_type, 5121 | 5123 | 5125) {
+            return Err(self.invalid(format!("accessors[{}]: indices must be unsigned integer scalars", index)));
+        }
+        Ok((0..elements.count).map(|element| elements.integer(element) as usize).collect())
+    }
+
+    fn vectors(&self, index: usize) -> Result<Vec<Vec3>, GltfError> {
+        let (values, components) = self.accessor(index)?;
+        if components < 3 {
+            return Err(self.invalid(format!("accessors[{}]: expected a VEC3 or VEC4", index)));
+        }
+        Ok(values.chunks_exact(components).map(|v| Vec3::new(v[0], v[1], v[2])).collect())
+    }
+
+    fn material(&self, index: usize) -> Result<Box<dyn Material>, GltfError> {
+        let material = item(self, &self.root.materials, "materials", index)?;
+        let pbr = &material.pbr_metallic_roughness;
+        let extensions = &material.extensions;
+
+        let [r, g, b, _] = pbr.base_color_factor;
+        let base = Vec3::new(r, g, b);
+        let [r, g, b] = material.emissive_factor;
+        let emissive = Vec3::new(r, g, b);
+        let strength = extensions.emissive_strength.as_ref().map_or(1.0, |e| e.emissive_strength);
+        let transmission = extensions.transmission.as_ref().map_or(0.0, |t| t.transmission_factor);
+
+        // Textures are ignored; the factors pick the closest of the renderer's materials.
+        Ok(if !emissive.near_zero() {
+            Box::new(DiffuseLight::new(emissive * strength))
+        } else if transmission > 0.5 {
+            let ior = extensions.ior.as_ref().map_or(default_ior(), |ior| ior.ior);
+            Box::new(Dielectric::new(ior, base))
+        } else if pbr.metallic_factor >= 0.5 {
+            Box::new(Metal::new(base, pbr.roughness_factor * pbr.roughness_factor))
+        } else {
+            Box::new(Lambertian::new(base))
+        })
+    }
+
+    fn add_primitive(
+        &self,
+        primitive: &PrimitiveDef,
+        transform: &Transform,
+        mesh: &mut Mesh,
+        materials: &mut HashMap<usize, usize>,
+    ) -> Result<(), GltfError> {
+        if primitive.mode != TRIANGLES {
+            // Points, lines and strips have nothing to render as surfaces.
+            return Ok(());
+        }
+        let attribute = |name: &str| primitive.attributes.get(name).copied();
+        let positions = match attribute("POSITION") {
+            Some(accessor) => self.vectors(accessor)?,
+            None => return Ok(()),
+        };
+        let normals = match attribute("NORMAL") {
+            Some(accessor) => Some(self.vectors(accessor)?),
+            None => None,
+        };
+        let uvs = match attribute("TEXCOORD_0") {
+            Some(accessor) => match self.accessor(accessor)? {
+                (uvs, 2) => Some(uvs),
+                _ => return Err(self.invalid(format!("accessors[{}]: expected a VEC2", accessor))),
+            },
+            None => None,
+        };
+        let colors = match attribute("COLOR_0") {
+            Some(accessor) => Some(self.vectors(accessor)?),
+            None => None,
+        };
+        let indices: Vec<usize> = match primitive.indices {
+            Some(accessor) => self.indices(accessor)?,
+            None => (0..positions.len()).collect(),
+        };
+        let matches = |len: Option<usize>| len.is_none_or(|len| len == positions.len());
+        if !matches(normals.as_ref().map(Vec::len))
+            || !matches(uvs.as_ref().map(|uvs| uvs.len() / 2))
+            || !matches(colors.as_ref().map(Vec::len))
+        {
+            return Err(self.invalid("vertex attributes differ in length".to_string()));
+        }
+        if let Some(bad) = indices.iter().find(|&&i| i >= positions.len()) {
+            return Err(self.invalid(format!("vertex index {} is out of range (0..{})", bad, positions.len())));
+        }
+
+        let material = match primitive.material {
+            Some(index) => match materials.get(&index) {
+                Some(material) => *material,
+                None => {
+                    let material = mesh.add_material(self.material(index)?);
+                    materials.insert(index, material);
+                    material
+                }
+            },
+            None => 0,
+        };
+
+        // Vertex attributes are shared by every face, so colors must cover the whole mesh or none of it.
+        let base = mesh.positions.len();
+        let normal_base = mesh.normals.len();
+        let uv_base = mesh.uvs.len();
+        if colors.is_some() {
+            mesh.colors.resize(base, Vec3::new(1.0, 1.0, 1.0));
+        }
+
+        mesh.positions.extend(positions.iter().map(|&p| transform.point(p)));
+        if let Some(normals) = &normals {
+            mesh.normals.extend(normals.iter().map(|&n| transform.normal(n)));
+        }
+        if let Some(uvs) = &uvs {
+            mesh.uvs.extend(uvs.chunks_exact(2).map(|uv| [uv[0], uv[1]]));
+        }
+        match &colors {
+            Some(colors) => mesh.colors.extend(colors),
+            None if !mesh.colors.is_empty() => mesh.colors.resize(mesh.positions.len(), Vec3::new(1.0, 1.0, 1.0)),
+            None => {}
+        }
+
+        // Mirroring transforms reverse the winding; swap two corners to keep faces outward.
+        let mirrored = transform.is_mirroring();
+        for triangle in indices.chunks_exact(3) {
+            let corners = if mirrored { [triangle[0], triangle[2], triangle[1]] } else { [triangle[0], triangle[1], triangle[2]] };
+            mesh.add_face(Face {
+                positions: corners.map(|i| base + i),
+                normals: normals.as_ref().map(|_| corners.map(|i| normal_base + i)),
+                uvs: uvs.as_ref().map(|_| corners.map(|i| uv_base + i)),
+                material,
+            });
+        }
+        Ok(())
+    }
+
+    // Walks the node hierarchy below `root` depth first, baking each node's world transform into
+    // the geometry it references. Nodes have at most one parent, so a node reached twice (through
+    // a cycle, a repeated child or a shared subtree) is an error rather than geometry to copy.
+    fn visit(
+        &self,
+        root: usize,
+        visited: &mut HashSet<usize>,
+        mesh: &mut Mesh,
+        materials: &mut HashMap<usize, usize>,
+        camera: &mut Option<Camera>,
+    ) -> Result<(), GltfError> {
+        let mut stack = vec![(root, Mat4::identity())];
+        while let Some((node_index, parent)) = stack.pop() {
+            if !visited.insert(node_index) {
+                return Err(self.invalid(format!("node {} has more than one parent", node_index)));
+            }
+            let node = item(self, &self.root.nodes, "nodes", node_index)?;
+            let matrix = parent * node.matrix();
+
+            // Nodes scaled to nothing are a common way to hide geometry; they have no inverse.
+            if let (Some(mesh_index), Some(transform)) = (node.mesh, Transform::new(matrix)) {
+                for primitive in &item(self, &self.root.meshes, "meshes", mesh_index)?.primitives {
+                    self.add_primitive(primitive, &transform, mesh, materials)?;
+                }
+            }
+
+            // The first perspective camera in the scene becomes the render camera.
+            if let (None, Some(index)) = (camera.as_ref(), node.camera)
+                && let Some(perspective) = &item(self, &self.root.cameras, "cameras", index)?.perspective
+            {
+                *camera = Some(Camera {
+                    position: matrix.transform_point(Vec3::new(0.0, 0.0, 0.0)),
+                    forward: matrix.transform_vector(Vec3::new(0.0, 0.0, -1.0)),
+                    up: matrix.transform_vector(Vec3::new(0.0, 1.0, 0.0)),
+                    yfov: perspective.yfov.to_degrees(),
+                });
+            }
+
+            // Reversed, so the first child is popped and visited first.
+            stack.extend(node.children.iter().rev().map(|&child| (child, matrix)));
+        }
+        Ok(())
+    }
+}
+
+fn import(path: &Path, default_material: Box<dyn Material>) -> Result<(Mesh, Option<Camera>), GltfError> {
+    let document = read_document(path)?;
+    import_document(&document, default_material)
+}
+
+fn import_document(document: &Document, default_material: Box<dyn Material>) -> Result<(Mesh, Option<Camera>), GltfError> {
+    let root = &document.root;
+
+    let roots: Vec<usize> = match &root.scenes {
+        Some(scenes) => item(document, scenes, "scenes", root.scene.unwrap_or(0))?.nodes.clone(),
+        // Without scenes, every node that isn't someone's child is a root.
+        None => {
+            let children: HashSet<usize> = root.nodes.iter().flat_map(|node| node.children.iter().copied()).collect();
+            (0..root.nodes.len()).filter(|i| !children.contains(i)).collect()
+        }
+    };
+
+    let mut mesh = Mesh::new();
+    mesh.add_material(default_material);
+    let mut materials = HashMap::new();
+    let mut camera = None;
+    let mut visited = HashSet::new();
+    for root in roots {
+        document.visit(root, &mut visited, &mut mesh, &mut materials, &mut camera)?;
+    }
+
+    if mesh.faces.is_empty() {
+        return Err(document.invalid("scene has no triangles".to_string()));
+    }
+    Ok((mesh, camera))
+}
+
+// Loads the geometry of a glTF scene as a single mesh; primitives without a material use `material`.
+pub fn load_mesh(path: &Path, material: Box<dyn Material>) -> Result<Mesh, GltfError> {
+    import(path, material).map(|(mesh, _)| mesh)
+}
+
+// Loads a glTF file as a whole scene, taking the camera from it when it has one.
+pub fn load(path: &Path, mut render: Render) -> Result<Scene, GltfError> {
+    let (mesh, camera) = import(path, Box::new(Lambertian::new(Vec3::new(0.8, 0.8, 0.8))))?;
+
+    if let Some(camera) = camera {
+        let lookat = camera.position + camera.forward;
+        render
+            .lookfrom(camera.position.x, camera.position.y, camera.position.z)
+            .lookat(lookat.x, lookat.y, lookat.z)
+            .vup(camera.up.x, camera.up.y, camera.up.z)
+            .vfov(camera.yfov)
+            .aperture(0.0);
+    }
+
+    let mut world = HittableList::new();
+    world.add(Box::new(mesh.into_bvh()));
+    Ok(Scene { world, instances: None, render })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base64(data: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut encoded = String::new();
+        for chunk in data.chunks(3) {
+            let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &b)| bits | (b as u32) << (16 - 8 * i));
+            for i in 0..=chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char);
+            }
+            encoded.push_str(&"=".repeat(3 - chunk.len()));
+        }
+        encoded
+    }
+
+    // A document with one buffer holding `data` and a single view over all of it.
+    fn document(data: &[u8], accessors: &str) -> Result<Document, GltfError> {
+        let json = format!(
+            r#"{{"buffers": [{{"uri": "data:application/octet-stream;base64,{}", "byteLength": {}}}],
+                "bufferViews": [{{"buffer": 0, "byteLength": {}}}, {{"buffer": 0, "byteOffset": 4, "byteLength": 4}}],
+                "accessors": [{}]}}"#,
+            base64(data),
+            data.len(),
+            data.len(),
+            accessors
+        );
+        parse_document(Path::new("test.gltf"), json.as_bytes())
+    }
+
+    // Imports a document whose `nodes` (and `scenes`, if given) place mesh 0, a single
+    // triangle, returning the number of faces in the scene.
+    fn import_nodes(nodes: &str) -> Result<usize, String> {
+        let data: Vec<u8> = [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let json = format!(
+            r#"{{"buffers": [{{"uri": "data:application/octet-stream;base64,{}", "byteLength": 36}}],
+                "bufferViews": [{{"buffer": 0, "byteLength": 36}}],
+                "accessors": [{{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}}],
+                "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}}}]}}],
+                {}}}"#,
+            base64(&data),
+            nodes
+        );
+        let document = parse_document(Path::new("test.gltf"), json.as_bytes()).map_err(|e| e.to_string())?;
+        let material = Box::new(crate::material::Lambertian::new(Vec3::new(1.0, 1.0, 1.0)));
+        import_document(&document, material).map(|(mesh, _)| mesh.faces.len()).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn large_indices_are_read_exactly() {
+        let data = [16_777_217u32, 4_000_000_001].map(u32::to_le_bytes).concat();
+        let document = document(&data, r#"{"bufferView": 0, "componentType": 5125, "count": 2, "type": "SCALAR"}"#).unwrap();
+        assert_eq!(document.indices(0).unwrap(), vec![16_777_217, 4_000_000_001]);
+    }
+
+    #[test]
+    fn accessors_stay_inside_their_view() {
+        // Fits in the 8-byte buffer, but not in the 4-byte second view.
+        let document = document(&[0; 8], r#"{"bufferView": 1, "componentType": 5126, "count": 2, "type": "SCALAR"}"#).unwrap();
+        assert!(document.accessor(0).is_err());
+    }
+
+    #[test]
+    fn huge_counts_are_rejected_before_allocating() {
+        let accessors = r#"{"componentType": 5126, "count": 18446744073709551615, "type": "VEC4"},
+            {"bufferView": 0, "componentType": 5126, "count": 18446744073709551615, "type": "VEC4"}"#;
+        let document = document(&[0; 8], accessors).unwrap();
+        assert!(document.accessor(0).is_err());
+        assert!(document.accessor(1).is_err());
+    }
+
+    #[test]
+    fn accessors_without_a_view_are_zeros() {
+        let document = document(&[], r#"{"componentType": 5126, "count": 2, "type": "VEC2"}"#).unwrap();
+        assert_eq!(document.accessor(0).unwrap(), (vec![0.0; 4], 2));
+    }
+
+    #[test]
+    fn base64_round_trips() {
+        assert_eq!(decode_base64("AAECAw==").unwrap(), vec![0, 1, 2, 3]);
+        assert_eq!(decode_base64("AAEC").unwrap(), vec![0, 1, 2]);
+        assert!(decode_base64("AAE*").is_none());
+        assert!(decode_base64("A===").is_none());
+    }
+
+    #[test]
+    fn only_base64_data_uris_are_accepted() {
+        let json = r#"{"buffers": [{"uri": "data:application/octet-stream,AAAA", "byteLength": 3}]}"#;
+        assert!(parse_document(Path::new("test.gltf"), json.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn uris_are_percent_decoded() {
+        assert_eq!(decode_uri("my%20mesh%25.bin").unwrap(), "my mesh%.bin");
+        assert_eq!(decode_uri("caf%C3%A9.bin").unwrap(), "café.bin");
+        assert!(decode_uri("bad%2").is_none());
+        assert!(decode_uri("bad%zz.bin").is_none());
+    }
+
+    #[test]
+    fn node_trees_are_walked_once() {
+        let tree = r#""nodes": [{"children": [1, 2]}, {"mesh": 0}, {"mesh": 0, "translation": [2, 0, 0], "children": [3]},
+            {"mesh": 0, "scale": [2, 2, 2]}]"#;
+        assert_eq!(import_nodes(tree), Ok(3));
+        assert_eq!(import_nodes(&format!(r#"{}, "scenes": [{{"nodes": [2]}}]"#, tree)), Ok(2));
+        // Without scenes every node nobody lists as a child is a root.
+        assert_eq!(import_nodes(r#""nodes": [{"mesh": 0}, {"children": [0]}, {"mesh": 0}]"#), Ok(2));
+    }
+
+    #[test]
+    fn nodes_with_more_than_one_parent_are_rejected() {
+        let error = |node: usize| Err(format!("test.gltf: node {} has more than one parent", node));
+        // The same child twice, and a subtree shared by two parents.
+        assert_eq!(import_nodes(r#""nodes": [{"children": [1, 1]}, {"mesh": 0}]"#), error(1));
+        assert_eq!(
+            import_nodes(r#""nodes": [{"children": [2]}, {"children": [2]}, {"mesh": 0, "children": [3]}, {"mesh": 0}]"#),
+            error(2),
+        );
+        // A node listed as a root and as a child, or twice as a root.
+        assert_eq!(import_nodes(r#""nodes": [{"children": [1]}, {"mesh": 0}], "scenes": [{"nodes": [0, 1]}]"#), error(1));
+        assert_eq!(import_nodes(r#""nodes": [{"mesh": 0}], "scenes": [{"nodes": [0, 0]}]"#), error(0));
+        // Cycles, including a node that is its own child.
+        assert_eq!(import_nodes(r#""nodes": [{"children": [1]}, {"mesh": 0, "children": [0]}], "scenes": [{"nodes": [0]}]"#), error(0));
+        assert_eq!(import_nodes(r#""nodes": [{"mesh": 0, "children": [0]}], "scenes": [{"nodes": [0]}]"#), error(0));
+    }
+
+    #[test]
+    fn deep_hierarchies_do_not_recurse() {
+        let chain: Vec<String> = (1..20_000).map(|i| format!(r#"{{"children": [{}]}}"#, i)).collect();
+        let nodes = format!(r#""nodes": [{}, {{"mesh": 0}}]"#, chain.join(", "));
+        assert_eq!(import_nodes(&nodes), Ok(1));
+    }
+
+    #[test]
+    fn malformed_json_is_an_error() {
+        assert!(parse_document(Path::new("test.gltf"), b"{\"nodes\": [").is_err());
+        assert!(parse_document(Path::new("test.gltf"), br#"{"nodes": [{"matrix": [1, 0]}]}"#).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use std::io::{self, Write};
+use crate::vec3::Vec3;
+
+const MIN_RLE_WIDTH: usize = 8;
+const MAX_RLE_WIDTH: usize = 0x7FFF;
+
+// Shared-exponent RGBE encoding of a linear color.
+fn rgbe(color: Vec3) -> [u8; 4] {
+    let r = color.r().max(0.0);
+    let g = color.g().max(0.0);
+    let b = color.b().max(0.0);
+    let max = r.max(g).max(b);
+
+    if !max.is_finite() || max < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    // max = mantissa * 2^exponent with mantissa in [0.5, 1).
+    let exponent = max.log2().floor() as i32 + 1;
+    let mut scale = 256.0 / 2f32.powi(exponent);
+    let mut exponent = exponent;
+    if max * scale >= 256.0 {
+        scale *= 0.5;
+        exponent += 1;
+    }
+    if exponent > 127 {
+        // The exponent byte would wrap past 2^127; saturate the mantissas instead.
+        exponent = 127;
+        scale = 256.0 / 2f32.powi(127);
+    }
+
+    [(r * scale) as u8, (g * scale) as u8, (b * scale) as u8, (exponent + 128) as u8]
+}
+
+// Run-length encodes one component of a scanline using the "new" Radiance scheme:
+// runs are a count above 128 followed by the repeated byte, literals a count up to 128.
+fn encode_component(data: &[u8], out: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < data.len() {
+        let mut run = 1;
+        while i + run < data.len() && run < 127 && data[i + run] == data[i] {
+            run += 1;
+        }
+
+        if run >= 3 {
+            out.push(128 + run as u8);
+            out.push(data[i]);
+            i += run;
+            continue;
+        }
+
+        // Collect literals until the next run of at least three equal bytes.
+        let start = i;
+        while i < data.len() && i - start < 128 {
+            if i + 2 < data.len() && data[i] == data[i + 1] && data[i] == data[i + 2] {
+                break;
+            }
+            i += 1;
+        }
+        out.push((i - start) as u8);
+        out.extend_from_slice(&data[start..i]);
+    }
+}
+
+// `pixels` holds linear RGB, row by row from the top of the image.
+pub fn write_hdr<W: Write>(w: &mut W, width: usize, height: usize, pixels: &[Vec3]) -> io::Result<()> {
+    write!(w, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", height, width)?;
+
+    let rle = (MIN_RLE_WIDTH..=MAX_RLE_WIDTH).contains(&width);
+    let mut components: [Vec<u8>; 4] = std::array::from_fn(|_| Vec::with_capacity(width));
+    let mut line = Vec::new();
+
+    for y in 0..height {
+        let row = &pixels[y * width..(y + 1) * width];
+        line.clear();
+
+        if !rle {
+            for pixel in row {
+                line.extend_from_slice(&rgbe(*pixel));
+            }
+            w.write_all(&line)?;
+            continue;
+        }
+
+        for component in components.iter_mut() {
+            component.clear();
+        }
+        for pixel in row {
+            for (component, value) in components.iter_mut().zip(rgbe(*pixel)) {
+                component.push(value);
+            }
+        }
+
+        line.extend_from_slice(&[2, 2, (width >> 8) as u8, (width & 0xFF) as u8]);
+        for component in &components {
+            encode_component(component, &mut line);
+        }
+        w.write_all(&line)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reads back one run-length encoded component of `len` bytes, returning it and the rest.
+    fn decode_component(mut data: &[u8], len: usize) -> (Vec<u8>, &[u8]) {
+        let mut out = Vec::new();
+        while out.len() < len {
+            let count = data[0] as usize;
+            if count > 128 {
+                out.extend(std::iter::repeat_n(data[1], count - 128));
+                data = &data[2..];
+            } else {
+                assert!(count > 0);
+                out.extend_from_slice(&data[1..1 + count]);
+                data = &data[1 + count..];
+            }
+        }
+        assert_eq!(out.len(), len);
+        (out, data)
+    }
+
+    fn encode(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode_component(data, &mut out);
+        out
+    }
+
+    #[test]
+    fn rgbe_shares_the_largest_exponent() {
+        assert_eq!(rgbe(Vec3::new(1.0, 1.0, 1.0)), [128, 128, 128, 129]);
+        assert_eq!(rgbe(Vec3::new(0.5, 0.25, 0.0)), [128, 64, 0, 128]);
+        assert_eq!(rgbe(Vec3::new(0.0, 0.0, 0.0)), [0, 0, 0, 0]);
+        assert_eq!(rgbe(Vec3::new(-1.0, f32::NAN, 0.0)), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn rgbe_saturates_instead_of_wrapping() {
+        assert_eq!(rgbe(Vec3::new(f32::MAX, f32::MAX, f32::MAX)), [255, 255, 255, 255]);
+        assert_eq!(rgbe(Vec3::new(f32::MAX, 0.0, 2f32.powi(126))), [255, 0, 128, 255]);
+        assert_eq!(rgbe(Vec3::new(2f32.powi(126), 0.0, 0.0)), [128, 0, 0, 255]);
+    }
+
+    #[test]
+    fn runs_are_capped_at_127() {
+        assert_eq!(encode(&[7; 127]), [255, 7]);
+        assert_eq!(encode(&[7; 128]), [255, 7, 1, 7]);
+        assert_eq!(encode(&[7; 130]), [255, 7, 131, 7]);
+        assert_eq!(encode(&[7; 3]), [131, 7]);
+    }
+
+    #[test]
+    fn literals_are_capped_at_128() {
+        let ramp: Vec<u8> = (0..=255).collect();
+        let encoded = encode(&ramp[..128]);
+        assert_eq!(encoded[0], 128);
+        assert_eq!(encoded[1..], ramp[..128]);
+
+        let encoded = encode(&ramp[..129]);
+        assert_eq!(encoded.len(), 131);
+        assert_eq!(encoded[129..], [1, 128]);
+
+        // Two equal bytes stay literal, three start a run.
+        assert_eq!(encode(&[1, 2, 2, 3]), [4, 1, 2, 2, 3]);
+        assert_eq!(encode(&[1, 2, 3, 3, 3, 3]), [2, 1, 2, 132, 3]);
+    }
+
+    #[test]
+    fn encoded_components_decode_back() {
+        let mut data = Vec::new();
+        for (value, len) in [(1u8, 1), (2, 2), (3, 127), (4, 128), (5, 129), (6, 3), (7, 300)] {
+            data.extend(std::iter::repeat_n(value, len));
+            data.extend((0..len as u32).map(|i| (i * 37 % 251) as u8));
+        }
+        let encoded = encode(&data);
+        let (decoded, rest) = decode_component(&encoded, data.len());
+        assert_eq!(decoded, data);
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn scanlines_switch_to_rle_from_eight_pixels() {
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 4\n";
+        let mut out = Vec::new();
+        write_hdr(&mut out, 4, 1, &[Vec3::new(1.0, 1.0, 1.0); 4]).unwrap();
+        assert_eq!(out[..header.len()], header[..]);
+        assert_eq!(out[header.len()..], [128, 128, 128, 129].repeat(4));
+
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 8\n";
+        let pixels: Vec<Vec3> = (0..16).map(|i| Vec3::new(i as f32 / 16.0, 0.5, 0.5)).collect();
+        let mut out = Vec::new();
+        write_hdr(&mut out, 8, 2, &pixels).unwrap();
+        assert_eq!(out[..header.len()], header[..]);
+
+        let mut rest = &out[header.len()..];
+        for row in pixels.chunks(8) {
+            assert_eq!(rest[..4], [2, 2, 0, 8]);
+            rest = &rest[4..];
+            for channel in 0..4 {
+                let (component, tail) = decode_component(rest, 8);
+                let expected: Vec<u8> = row.iter().map(|&pixel| rgbe(pixel)[channel]).collect();
+                assert_eq!(component, expected);
+                rest = tail;
+            }
+        }
+        assert!(rest.is_empty());
+    }
+}
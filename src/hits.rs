@@ -1,16 +1,65 @@
+use crate::bounds::BBox;
 use crate::material::Material;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 
+#[derive(Copy, Clone)]
 pub struct HitRecord<'a> {
     pub t: f32,
     pub p: Vec3,
+    // Points out of closed objects and along the geometric normal of open surfaces, whichever
+    // side the ray came from; materials that only care about that side flip it themselves.
     pub normal: Vec3,
+    // Surface coordinates of the hit: barycentric weights of the second and third vertex on
+    // triangles and mesh faces, positions along the two edges on quads.
+    pub u: f32,
+    pub v: f32,
+    // Interpolated texture coordinates of mesh faces that carry them.
+    pub uv: Option<[f32; 2]>,
+    // Interpolated vertex color of meshes that carry one; tints diffuse albedo.
+    pub color: Option<Vec3>,
     pub material: &'a dyn Material,
 }
 
+// A stretch of a ray inside a closed object, from where it enters to where it leaves.
+#[derive(Copy, Clone)]
+pub struct Span<'a> {
+    pub enter: HitRecord<'a>,
+    pub exit: HitRecord<'a>,
+}
+
 pub trait Hittable: Send + Sync {
     fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>>;
+    fn bounding_box(&self) -> BBox;
+
+    // Every span of the ray's whole line (negative t included) inside the object, in order.
+    // Only closed objects have an inside; anything else has no spans and is empty to CSG.
+    fn spans(&self, _ray: &Ray) -> Vec<Span<'_>> {
+        Vec::new()
+    }
+}
+
+// Spans of a closed object, found by walking its surface along the ray: it is entered
+// where the ray meets an outward normal head on and left where it doesn't.
+pub fn surface_spans<'a, H: Hittable + ?Sized>(object: &'a H, ray: &Ray) -> Vec<Span<'a>> {
+    let mut spans = Vec::new();
+    let mut enter = None;
+    let mut t = f32::NEG_INFINITY;
+
+    while let Some(rec) = object.hit(ray, t, f32::INFINITY) {
+        t = rec.t;
+        let entering = rec.normal.dot(ray.direction()) < 0.0;
+        match enter {
+            None if entering => enter = Some(rec),
+            Some(enter_rec) if !entering => {
+                spans.push(Span { enter: enter_rec, exit: rec });
+                enter = None;
+            }
+            // A grazing ray can meet two faces at an edge; the repeat changes nothing.
+            _ => {}
+        }
+    }
+    spans
 }
 
 pub struct HittableList {
@@ -41,4 +90,44 @@ impl Hittable for HittableList {
 
         hit_anything
     }
+
+    fn bounding_box(&self) -> BBox {
+        self.objects
+            .iter()
+            .fold(BBox::default(), |bbox, object| BBox::surrounding(&bbox, &object.bounding_box()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::sphere::Sphere;
+
+    fn unit_sphere() -> Sphere {
+        Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0, Box::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))))
+    }
+
+    #[test]
+    fn surface_spans_pair_entries_with_exits() {
+        let sphere = unit_sphere();
+        let ray = Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        let spans = surface_spans(&sphere, &ray);
+        assert_eq!(spans.len(), 1);
+        assert!((spans[0].enter.t - 4.0).abs() < 1e-5 && (spans[0].exit.t - 6.0).abs() < 1e-5);
+        assert!(spans[0].enter.normal.z < -0.99 && spans[0].exit.normal.z > 0.99);
+
+        let miss = Ray::new(Vec3::new(0.0, 2.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(surface_spans(&sphere, &miss).is_empty());
+    }
+
+    #[test]
+    fn surface_spans_reach_behind_the_origin() {
+        // A ray starting inside is already in a span that began behind it.
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        let sphere = unit_sphere();
+        let spans = surface_spans(&sphere, &ray);
+        assert_eq!(spans.len(), 1);
+        assert!((spans[0].enter.t + 1.0).abs() < 1e-5 && (spans[0].exit.t - 1.0).abs() < 1e-5);
+    }
 }
@@ -0,0 +1,156 @@
+use std::io::{self, Write};
+use std::path::Path;
+use crate::exr::{write_exr, PixelType};
+use crate::hdr::write_hdr;
+use crate::png::write_png;
+use crate::vec3::Vec3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ImageFormat {
+    Ppm,
+    PpmAscii,
+    Png,
+    Png16,
+    Exr,
+    ExrFloat,
+    Hdr,
+}
+
+impl ImageFormat {
+    pub fn from_name(name: &str) -> Option<ImageFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "ppm-ascii" => Some(ImageFormat::PpmAscii),
+            "png" => Some(ImageFormat::Png),
+            "png16" => Some(ImageFormat::Png16),
+            "exr" => Some(ImageFormat::Exr),
+            "exr32" => Some(ImageFormat::ExrFloat),
+            "hdr" => Some(ImageFormat::Hdr),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<ImageFormat> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            "exr" => Some(ImageFormat::Exr),
+            "hdr" => Some(ImageFormat::Hdr),
+            _ => None,
+        }
+    }
+}
+
+pub fn srgb_encode(linear: f32) -> f32 {
+    let c = linear.clamp(0.0, 1.0);
+    if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+pub fn quantize8(linear: f32) -> u8 {
+    (srgb_encode(linear) * 255.0 + 0.5) as u8
+}
+
+pub fn quantize16(linear: f32) -> u16 {
+    (srgb_encode(linear) * 65535.0 + 0.5) as u16
+}
+
+pub fn write_ppm<W: Write>(w: &mut W, width: usize, height: usize, pixels: &[Vec3]) -> io::Result<()> {
+    write!(w, "P6\n{} {}\n255\n", width, height)?;
+    let mut row = Vec::with_capacity(3 * width);
+    for y in 0..height {
+        row.clear();
+        for pixel in &pixels[y * width..(y + 1) * width] {
+            row.extend_from_slice(&[quantize8(pixel.r()), quantize8(pixel.g()), quantize8(pixel.b())]);
+        }
+        w.write_all(&row)?;
+    }
+    Ok(())
+}
+
+pub fn write_ppm_ascii<W: Write>(w: &mut W, width: usize, height: usize, pixels: &[Vec3]) -> io::Result<()> {
+    writeln!(w, "P3\n{} {}\n255", width, height)?;
+    for pixel in &pixels[..width * height] {
+        writeln!(w, "{} {} {}", quantize8(pixel.r()), quantize8(pixel.g()), quantize8(pixel.b()))?;
+    }
+    Ok(())
+}
+
+fn write_rgb_exr<W: Write>(w: &mut W, width: usize, height: usize, pixels: &[Vec3], pixel_type: PixelType) -> io::Result<()> {
+    let pixels = &pixels[..width * height];
+    let r: Vec<f32> = pixels.iter().map(|p| p.r()).collect();
+    let g: Vec<f32> = pixels.iter().map(|p| p.g()).collect();
+    let b: Vec<f32> = pixels.iter().map(|p| p.b()).collect();
+    write_exr(w, width, height, &[("R", &r), ("G", &g), ("B", &b)], pixel_type)
+}
+
+// `pixels` holds linear RGB, row by row from the top of the image.
+pub fn write<W: Write>(w: &mut W, format: ImageFormat, width: usize, height: usize, pixels: &[Vec3]) -> io::Result<()> {
+    match format {
+        ImageFormat::Ppm => write_ppm(w, width, height, pixels)?,
+        ImageFormat::PpmAscii => write_ppm_ascii(w, width, height, pixels)?,
+        ImageFormat::Png => write_png(w, width, height, pixels, false)?,
+        ImageFormat::Png16 => write_png(w, width, height, pixels, true)?,
+        ImageFormat::Exr => write_rgb_exr(w, width, height, pixels, PixelType::Half)?,
+        ImageFormat::ExrFloat => write_rgb_exr(w, width, height, pixels, PixelType::Float)?,
+        ImageFormat::Hdr => write_hdr(w, width, height, pixels)?,
+    }
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantization_clamps_and_encodes_srgb() {
+        assert_eq!(quantize8(0.0), 0);
+        assert_eq!(quantize8(-1.0), 0);
+        assert_eq!(quantize8(1.0), 255);
+        assert_eq!(quantize8(8.0), 255);
+        assert_eq!(quantize8(0.5), 188);
+        assert_eq!(quantize16(1.0), 65535);
+        assert_eq!(quantize16(0.0), 0);
+    }
+
+    #[test]
+    fn p6_writes_rows_top_down() {
+        let pixels = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.5, 2.0, -1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(0.0, 0.0, 0.0),
+        ];
+        let mut out = Vec::new();
+        write_ppm(&mut out, 3, 2, &pixels).unwrap();
+
+        let header = b"P6\n3 2\n255\n";
+        assert_eq!(out[..header.len()], header[..]);
+        assert_eq!(
+            out[header.len()..],
+            [255, 0, 0, 0, 255, 0, 0, 0, 255, 188, 255, 0, 255, 255, 255, 0, 0, 0],
+        );
+    }
+
+    #[test]
+    fn p3_writes_one_pixel_per_line() {
+        let mut out = Vec::new();
+        write_ppm_ascii(&mut out, 2, 1, &[Vec3::new(1.0, 0.0, 0.5), Vec3::new(0.0, 0.0, 0.0)]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "P3\n2 1\n255\n255 0 188\n0 0 0\n");
+    }
+
+    #[test]
+    fn formats_come_from_names_and_extensions() {
+        assert_eq!(ImageFormat::from_name("PPM-ascii"), Some(ImageFormat::PpmAscii));
+        assert_eq!(ImageFormat::from_name("exr32"), Some(ImageFormat::ExrFloat));
+        assert_eq!(ImageFormat::from_name("jpg"), None);
+        assert_eq!(ImageFormat::from_path(Path::new("out.PNG")), Some(ImageFormat::Png));
+        assert_eq!(ImageFormat::from_path(Path::new("out.jpg")), None);
+        assert_eq!(ImageFormat::from_path(Path::new("out")), None);
+    }
+}
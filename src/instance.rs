@@ -0,0 +1,106 @@
+use std::sync::Arc;
+use crate::bounds::BBox;
+use crate::hits::{HitRecord, Hittable, Span};
+use crate::ray::Ray;
+use crate::transform::Transform;
+use crate::vec3::Vec3;
+
+// Places shared geometry in the world. The ray is carried into object space instead of
+// the object into world space, so any number of instances can share one object.
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    transform: Transform,
+    bbox: BBox,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform) -> Instance {
+        let bbox = Instance::transformed_bounds(object.as_ref(), &transform);
+        Instance { object, transform, bbox }
+    }
+
+    fn transformed_bounds(object: &dyn Hittable, transform: &Transform) -> BBox {
+        let bounds = object.bounding_box();
+        if bounds.is_bounded() {
+            // Bound the transformed corners of the object's box.
+            let mut bbox = BBox::default();
+            for corner in 0..8 {
+                let pick = |axis: usize| {
+                    let interval = bounds[axis];
+                    (if corner >> axis & 1 == 0 { interval.min } else { interval.max }) as f32
+                };
+                let p = transform.point(Vec3::new(pick(0), pick(1), pick(2)));
+                bbox = BBox::surrounding(&bbox, &BBox::from_points(p, p));
+            }
+            bbox
+        } else {
+            BBox::universe()
+        }
+    }
+
+    // The direction is not renormalized, so distances along both rays agree.
+    fn local_ray(&self, ray: &Ray) -> Ray {
+        let inverse = &self.transform.inverse;
+        Ray::with_time(inverse.transform_point(ray.origin()), inverse.transform_vector(ray.direction()), ray.time())
+    }
+
+    fn to_world(&self, rec: &mut HitRecord) {
+        rec.p = self.transform.point(rec.p);
+        rec.normal = self.transform.normal(rec.normal);
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let mut rec = self.object.hit(&self.local_ray(ray), t_min, t_max)?;
+        self.to_world(&mut rec);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> BBox {
+        self.bbox
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        let mut spans = self.object.spans(&self.local_ray(ray));
+        for span in &mut spans {
+            self.to_world(&mut span.enter);
+            self.to_world(&mut span.exit);
+        }
+        spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csg::{Csg, Operation};
+    use crate::cylinder::Cylinder;
+    use crate::material::Lambertian;
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn spans_are_carried_back_to_world_space() {
+        // A unit sphere with a hole along z, doubled, turned so the hole runs along x and moved to z = 10.
+        let material = || Box::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        let sphere = Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0, material());
+        let hole = Cylinder::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 0.5, 4.0, material());
+        let csg = Csg::new(Operation::Difference, Box::new(sphere), Box::new(hole));
+        let transform = Transform::from_trs(Vec3::new(0.0, 0.0, 10.0), Vec3::new(0.0, 90.0, 0.0), Vec3::new(2.0, 2.0, 2.0));
+        let instance = Instance::new(Arc::new(csg), transform.unwrap());
+
+        // Across the hole: in and out of the sphere's wall on either side of it.
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 1.0));
+        let spans = instance.spans(&ray);
+        assert_eq!(spans.len(), 2);
+        for (span, [enter, exit]) in spans.iter().zip([[3.0, 4.0], [6.0, 7.0]]) {
+            assert!((span.enter.t - enter).abs() < 1e-4 && (span.exit.t - exit).abs() < 1e-4);
+            assert!((span.enter.p - ray.at(enter)).length() < 1e-4 && (span.exit.p - ray.at(exit)).length() < 1e-4);
+            assert!(span.enter.normal.z < -0.99 && span.exit.normal.z > 0.99);
+        }
+
+        // Down the hole, which now runs along x.
+        let ray = Ray::new(Vec3::new(-5.0, 0.0, 10.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(instance.spans(&ray).is_empty());
+    }
+}
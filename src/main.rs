@@ -1,6 +1,11 @@
+use std::env;
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+use crate::cli::Command;
+use crate::progress::ProgressFormat;
 use crate::hits::{HittableList};
-use crate::material::{Dielectric, Lambertian, Metal};
-use rand::Rng;
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Metal};
 
 mod vec3;
 mod ray;
@@ -11,10 +16,46 @@ mod material;
 mod render;
 mod color;
 mod bounds;
+mod bvh;
+mod scene;
+mod rt;
+mod toml_scene;
+mod random;
+mod cli;
+mod image;
+mod png;
+mod exr;
+mod hdr;
+mod framebuffer;
+mod scheduler;
+mod progress;
+mod plane;
+mod cylinder;
+mod cone;
+mod triangle;
+mod mesh;
+mod obj;
+mod ply;
+mod stl;
+mod gltf;
+mod transform;
+mod instance;
+mod quad;
+mod disk;
+mod torus;
+mod csg;
+mod medium;
+mod cuboid;
 
 use crate::vec3::Vec3;
 use crate::render::Render;
 use crate::sphere::Sphere;
+use crate::plane::Plane;
+use crate::bvh::Bvh;
+use crate::cuboid::Cuboid;
+use crate::instance::Instance;
+use crate::quad::Quad;
+use crate::transform::Transform;
 
 fn simple_world() -> HittableList {
     let mut world = HittableList::new();
@@ -38,17 +79,15 @@ fn random_scene() -> HittableList {
     let mut world = HittableList::new();
 
     let ground_material = Box::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
-    world.add(Box::new(Sphere::new(Vec3::new(0.0, -1000.0, 0.0), 1000.0, ground_material)));
-
-    let mut rng = rand::rng();
+    world.add(Box::new(Plane::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), ground_material)));
 
     for a in -11..11 {
         for b in -11..11 {
-            let choose_mat = rng.random::<f32>();
+            let choose_mat = random::random_f32();
             let center = Vec3::new(
-                a as f32 + 0.9 * rng.random::<f32>(),
+                a as f32 + 0.9 * random::random_f32(),
                 0.2,
-                b as f32 + 0.9 * rng.random::<f32>()
+                b as f32 + 0.9 * random::random_f32()
             );
 
             if (center - Vec3::new(4.0, 0.2, 0.0)).length() > 0.9 {
@@ -60,7 +99,7 @@ fn random_scene() -> HittableList {
                 } else if choose_mat < 0.95 {
                     // metal
                     let albedo = Vec3::random_range(0.5, 1.0);
-                    let fuzz = rng.random_range(0.0..0.5);
+                    let fuzz = random::random_range(0.0, 0.5);
                     let sphere_material = Box::new(Metal::new(albedo, fuzz));
                     world.add(Box::new(Sphere::new(center, 0.2, sphere_material)));
                 } else {
@@ -84,17 +123,121 @@ fn random_scene() -> HittableList {
     world
 }
 
+fn cornell_box() -> HittableList {
+    let mut world = HittableList::new();
+
+    let red = || Box::new(Lambertian::new(Vec3::new(0.65, 0.05, 0.05)));
+    let white = || Box::new(Lambertian::new(Vec3::new(0.73, 0.73, 0.73)));
+    let green = || Box::new(Lambertian::new(Vec3::new(0.12, 0.45, 0.15)));
+    let light = Box::new(DiffuseLight::new(Vec3::new(15.0, 15.0, 15.0)));
+
+    world.add(Box::new(Quad::new(Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), green())));
+    world.add(Box::new(Quad::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), red())));
+    world.add(Box::new(Quad::new(Vec3::new(343.0, 554.0, 332.0), Vec3::new(-130.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -105.0), light)));
+    world.add(Box::new(Quad::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 555.0), white())));
+    world.add(Box::new(Quad::new(Vec3::new(555.0, 555.0, 555.0), Vec3::new(-555.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -555.0), white())));
+    world.add(Box::new(Quad::new(Vec3::new(0.0, 0.0, 555.0), Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), white())));
+
+    let boxes = [
+        (Vec3::new(165.0, 330.0, 165.0), Vec3::new(265.0, 0.0, 295.0), 15.0),
+        (Vec3::new(165.0, 165.0, 165.0), Vec3::new(130.0, 0.0, 65.0), -18.0),
+    ];
+    for (size, position, angle) in boxes {
+        let cuboid = Arc::new(Cuboid::new(Vec3::new(0.0, 0.0, 0.0), size, white()));
+        let transform = Transform::from_trs(position, Vec3::new(0.0, angle, 0.0), Vec3::new(1.0, 1.0, 1.0)).unwrap();
+        world.add(Box::new(Instance::new(cuboid, transform)));
+    }
+
+    world
+}
+
 fn main() {
-    let world = random_scene();
-
-    let mut render = Render::new( 3840, 2160, 1000);
-    render
-        .lookfrom(13.0, 2.0, 3.0)
-        .lookat(0.0, 0.0, 0.0)
-        .vup(0.0, 1.0, 0.0)
-        .vfov(20.0)
-        .aperture(0.6)
-        .focus_dist(10.0)
-        .ipd(0.06)
-        .render_scene(world, true);
+    let args = match cli::parse(env::args().skip(1)) {
+        Ok(Command::Render(args)) => args,
+        Ok(Command::Help) => {
+            print!("{}", cli::USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\nFor more information, try '--help'.", e);
+            process::exit(2);
+        }
+    };
+
+    if let Some(seed) = args.seed {
+        random::seed(seed);
+    }
+
+    let mut render = Render::new(args.width, args.height, args.samples);
+    render.max_depth(args.max_depth).output(&args.output);
+    if let Some(format) = args.format {
+        render.format(format);
+    }
+    render.tile_size(args.tile_size).tile_order(args.tile_order).progress(args.progress);
+    if let Some(threads) = args.threads {
+        render.threads(threads);
+    }
+    if let Some(seed) = args.seed {
+        render.seed(seed);
+    }
+
+    let (mut scene, instances, render) = match args.scene.as_deref() {
+        None | Some("random") => {
+            render
+                .lookfrom(13.0, 2.0, 3.0)
+                .lookat(0.0, 0.0, 0.0)
+                .vup(0.0, 1.0, 0.0)
+                .vfov(20.0)
+                .aperture(0.6)
+                .focus_dist(10.0)
+                .ipd(0.06);
+            (random_scene(), None, render)
+        }
+        Some("simple") => {
+            render
+                .lookfrom(-2.0, 2.0, 1.0)
+                .lookat(0.0, 0.0, -1.0)
+                .vup(0.0, 1.0, 0.0)
+                .vfov(20.0)
+                .focus_dist(3.4);
+            (simple_world(), None, render)
+        }
+        Some("cornell") => {
+            render
+                .lookfrom(278.0, 278.0, -800.0)
+                .lookat(278.0, 278.0, 0.0)
+                .vup(0.0, 1.0, 0.0)
+                .vfov(40.0)
+                .aperture(0.0)
+                .background(0.0, 0.0, 0.0);
+            (cornell_box(), None, render)
+        }
+        Some(path) => match scene::load(Path::new(path), render) {
+            Ok(scene) => (scene.world, scene.instances, scene.render),
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                process::exit(1);
+            }
+        },
+    };
+
+    if let Some(tlas) = instances {
+        if args.progress == ProgressFormat::Human {
+            eprintln!("TLAS: {}", tlas.stats());
+        }
+        scene.add(Box::new(tlas));
+    }
+
+    let bvh = Bvh::new(scene);
+    if args.progress == ProgressFormat::Human {
+        eprintln!("BVH: {}", bvh.stats());
+    }
+
+    let mut world = HittableList::new();
+    world.add(Box::new(bvh));
+
+    if let Err(e) = render.render_scene(world, args.anaglyph) {
+        eprintln!("{}: {}", args.output.display(), e);
+        process::exit(1);
+    }
 }
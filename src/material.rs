@@ -1,10 +1,26 @@
-use rand::{rng, Rng};
+use std::sync::Arc;
+use crate::random;
 use crate::ray::Ray;
 use crate::hits::HitRecord;
 use crate::vec3::Vec3;
 
 pub trait Material: Send + Sync {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)>;
+
+    fn emitted(&self) -> Vec3 {
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+}
+
+// Lets many objects share one material.
+impl<M: Material + ?Sized> Material for Arc<M> {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
+        (**self).scatter(r_in, rec)
+    }
+
+    fn emitted(&self) -> Vec3 {
+        (**self).emitted()
+    }
 }
 
 pub struct Lambertian {
@@ -17,16 +33,23 @@ impl Lambertian {
     }
 }
 
+// The normal turned to face the incoming ray, so back faces of open surfaces reflect too.
+fn facing(r_in: &Ray, rec: &HitRecord) -> Vec3 {
+    if r_in.direction().dot(rec.normal) < 0.0 { rec.normal } else { -rec.normal }
+}
+
 impl Material for Lambertian {
-    fn scatter(&self, _r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
-        let mut scatter_direction = rec.normal + Vec3::random_unit_vector();
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
+        let normal = facing(r_in, rec);
+        let mut scatter_direction = normal + Vec3::random_unit_vector();
 
         if scatter_direction.near_zero() {
-            scatter_direction = rec.normal;
+            scatter_direction = normal;
         }
 
-        let scattered = Ray::new(rec.p, scatter_direction);
-        Some((self.albedo, scattered))
+        let scattered = Ray::with_time(rec.p, scatter_direction, r_in.time());
+        let albedo = rec.color.map_or(self.albedo, |color| self.albedo * color);
+        Some((albedo, scattered))
     }
 }
 
@@ -43,10 +66,11 @@ impl Metal {
 
 impl Material for Metal {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
-        let reflected = r_in.direction().unit_vector().reflect(rec.normal);
-        let scattered = Ray::new(rec.p, reflected + Vec3::random_in_unit_sphere() * self.fuzz);
+        let normal = facing(r_in, rec);
+        let reflected = r_in.direction().unit_vector().reflect(normal);
+        let scattered = Ray::with_time(rec.p, reflected + Vec3::random_in_unit_sphere() * self.fuzz, r_in.time());
 
-        if scattered.direction().dot(rec.normal) > 0.0 {
+        if scattered.direction().dot(normal) > 0.0 {
             Some((self.albedo, scattered))
         } else {
             None
@@ -88,8 +112,7 @@ impl Material for Dielectric {
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
         let direction;
 
-        let mut rng = rng();
-        if cannot_refract || Dielectric::reflectance(cos_theta, refraction_ratio) > rng.random() {
+        if cannot_refract || Dielectric::reflectance(cos_theta, refraction_ratio) > random::random_f32() {
             direction = unit_direction.reflect(normal);
         } else {
             if let Some(refracted) = unit_direction.refract(normal, refraction_ratio) {
@@ -99,7 +122,45 @@ impl Material for Dielectric {
             }
         }
 
-        let scattered = Ray::new(rec.p, direction);
+        let scattered = Ray::with_time(rec.p, direction, r_in.time());
+        Some((self.albedo, scattered))
+    }
+}
+
+pub struct DiffuseLight {
+    pub emit: Vec3,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Vec3) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _r_in: &Ray, _rec: &HitRecord) -> Option<(Vec3, Ray)> {
+        None
+    }
+
+    fn emitted(&self) -> Vec3 {
+        self.emit
+    }
+}
+
+// Scatters uniformly in every direction, as the particles of a participating medium do.
+pub struct Isotropic {
+    pub albedo: Vec3,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Vec3) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
+        let scattered = Ray::with_time(rec.p, Vec3::random_unit_vector(), r_in.time());
         Some((self.albedo, scattered))
     }
 }
@@ -0,0 +1,59 @@
+use crate::bounds::BBox;
+use crate::hits::{HitRecord, Hittable};
+use crate::material::{Isotropic, Material};
+use crate::random;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// Smoke, fog or anything else of constant density filling a closed boundary. Rays
+// passing through scatter at a random distance, more likely the denser the medium.
+pub struct ConstantMedium {
+    pub boundary: Box<dyn Hittable>,
+    pub density: f32,
+    pub phase_function: Box<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Box<dyn Hittable>, density: f32, albedo: Vec3) -> ConstantMedium {
+        ConstantMedium { boundary, density, phase_function: Box::new(Isotropic::new(albedo)) }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        // The free path is exponentially distributed and doesn't depend on the distance already
+        // covered, so one draw can be spent across every stretch of the boundary the ray is inside.
+        let length = ray.direction().length();
+        let mut remaining = -(1.0 - random::random_f32()).ln() / self.density;
+
+        for span in self.boundary.spans(ray) {
+            let enter = span.enter.t.max(t_min);
+            let exit = span.exit.t.min(t_max);
+            if enter >= exit {
+                continue;
+            }
+
+            let inside = (exit - enter) * length;
+            if remaining < inside {
+                let t = enter + remaining / length;
+                return Some(HitRecord {
+                    t,
+                    p: ray.at(t),
+                    // Arbitrary: isotropic scattering doesn't look at it.
+                    normal: Vec3::new(1.0, 0.0, 0.0),
+                    u: 0.0,
+                    v: 0.0,
+                    uv: None,
+                    color: None,
+                    material: self.phase_function.as_ref(),
+                });
+            }
+            remaining -= inside;
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> BBox {
+        self.boundary.bounding_box()
+    }
+}
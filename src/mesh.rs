@@ -0,0 +1,149 @@
+use std::sync::Arc;
+use crate::bounds::BBox;
+use crate::bvh::Bvh;
+use crate::hits::{HitRecord, Hittable, HittableList};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::triangle;
+use crate::vec3::Vec3;
+
+// Indices into the mesh's attribute arrays for the three corners of a triangle.
+pub struct Face {
+    pub positions: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+    pub material: usize,
+}
+
+// A triangle mesh whose faces share vertex attributes instead of copying them.
+pub struct Mesh {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<[f32; 2]>,
+    // Either empty or one color per position.
+    pub colors: Vec<Vec3>,
+    pub faces: Vec<Face>,
+    pub materials: Vec<Box<dyn Material>>,
+}
+
+impl Mesh {
+    pub fn new() -> Mesh {
+        Mesh {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            colors: Vec::new(),
+            faces: Vec::new(),
+            materials: Vec::new(),
+        }
+    }
+
+    pub fn add_material(&mut self, material: Box<dyn Material>) -> usize {
+        self.materials.push(material);
+        self.materials.len() - 1
+    }
+
+    // Faces with a zero-area triangle can never be hit and are left out.
+    pub fn add_face(&mut self, face: Face) {
+        let [a, b, c] = face.positions.map(|i| self.positions[i]);
+        if !(b - a).cross(c - a).near_zero() {
+            self.faces.push(face);
+        }
+    }
+
+    // Builds a BVH over the faces; every triangle in it shares this mesh's data.
+    pub fn into_bvh(self) -> Bvh {
+        let mesh = Arc::new(self);
+        let mut triangles = HittableList::new();
+        for face in 0..mesh.faces.len() {
+            triangles.add(Box::new(MeshTriangle { mesh: mesh.clone(), face }));
+        }
+        Bvh::new(triangles)
+    }
+}
+
+struct MeshTriangle {
+    mesh: Arc<Mesh>,
+    face: usize,
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> [Vec3; 3] {
+        self.mesh.faces[self.face].positions.map(|i| self.mesh.positions[i])
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let face = &self.mesh.faces[self.face];
+        let [a, b, c] = self.vertices();
+        let (t, u, v) = triangle::intersect(ray, a, b, c, t_min, t_max)?;
+
+        let mut rec = HitRecord {
+            t,
+            p: ray.at(t),
+            normal: (b - a).cross(c - a).unit_vector(),
+            u,
+            v,
+            uv: None,
+            color: None,
+            material: self.mesh.materials[face.material].as_ref(),
+        };
+
+        // Smooth shading: blend the vertex normals with the barycentric weights.
+        if let Some(normals) = face.normals {
+            let [na, nb, nc] = normals.map(|i| self.mesh.normals[i]);
+            let normal = na * (1.0 - rec.u - rec.v) + nb * rec.u + nc * rec.v;
+            if !normal.near_zero() {
+                rec.normal = normal.unit_vector();
+            }
+        }
+        if !self.mesh.colors.is_empty() {
+            let [ca, cb, cc] = face.positions.map(|i| self.mesh.colors[i]);
+            rec.color = Some(ca * (1.0 - rec.u - rec.v) + cb * rec.u + cc * rec.v);
+        }
+        if let Some(uvs) = face.uvs {
+            let weights = [1.0 - u - v, u, v];
+            let [ta, tb, tc] = uvs.map(|i| self.mesh.uvs[i]);
+            rec.uv = Some(std::array::from_fn(|k| weights[0] * ta[k] + weights[1] * tb[k] + weights[2] * tc[k]));
+        }
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> BBox {
+        let [a, b, c] = self.vertices();
+        BBox::surrounding(&BBox::from_points(a, b), &BBox::from_points(c, c)).pad()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn triangle(uvs: Option<[usize; 3]>) -> Bvh {
+        let mut mesh = Mesh::new();
+        mesh.add_material(Box::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))));
+        mesh.positions = vec![Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0)];
+        mesh.uvs = vec![[0.5, 0.5], [1.0, 0.5], [0.5, 1.0]];
+        mesh.colors = vec![Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)];
+        mesh.add_face(Face { positions: [0, 1, 2], normals: None, uvs, material: 0 });
+        mesh.into_bvh()
+    }
+
+    #[test]
+    fn texture_coordinates_leave_the_barycentric_weights_alone() {
+        let ray = Ray::new(Vec3::new(0.5, 1.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        for uvs in [None, Some([0, 1, 2])] {
+            let bvh = triangle(uvs);
+            let hit = bvh.hit(&ray, 0.001, f32::INFINITY).unwrap();
+            assert!((hit.u - 0.25).abs() < 1e-6 && (hit.v - 0.5).abs() < 1e-6);
+            assert!((hit.color.unwrap() - Vec3::new(0.25, 0.25, 0.5)).length() < 1e-6);
+            match (uvs, hit.uv) {
+                (None, None) => {}
+                (Some(_), Some([s, t])) => assert!((s - 0.625).abs() < 1e-6 && (t - 0.75).abs() < 1e-6),
+                _ => panic!("texture coordinates only come from faces that have them"),
+            }
+        }
+    }
+}
@@ -0,0 +1,442 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::mesh::{Face, Mesh};
+use crate::vec3::Vec3;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(PathBuf, io::Error),
+    Parse { path: PathBuf, line: usize, message: String },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            ObjError::Parse { path, line, message } => write!(f, "{}:{}: {}", path.display(), line, message),
+        }
+    }
+}
+
+impl Error for ObjError {}
+
+fn read(path: &Path) -> Result<String, ObjError> {
+    fs::read_to_string(path).map_err(|e| ObjError::Io(path.to_path_buf(), e))
+}
+
+struct Statement<'a> {
+    path: &'a Path,
+    line: usize,
+    keyword: &'a str,
+    args: Vec<&'a str>,
+}
+
+impl<'a> Statement<'a> {
+    fn error(&self, message: String) -> ObjError {
+        ObjError::Parse { path: self.path.to_path_buf(), line: self.line, message }
+    }
+
+    fn float(&self, index: usize, default: Option<f32>) -> Result<f32, ObjError> {
+        match (self.args.get(index), default) {
+            (Some(text), _) => match text.parse::<f32>() {
+                Ok(value) if value.is_finite() => Ok(value),
+                _ => Err(self.error(format!("invalid number '{}' in '{}'", text, self.keyword))),
+            },
+            (None, Some(value)) => Ok(value),
+            (None, None) => Err(self.error(format!("'{}' needs at least {} values", self.keyword, index + 1))),
+        }
+    }
+
+    fn vector(&self) -> Result<Vec3, ObjError> {
+        Ok(Vec3::new(self.float(0, None)?, self.float(1, None)?, self.float(2, None)?))
+    }
+
+    // MTL colors may give a single value for all three channels.
+    fn color(&self) -> Result<Vec3, ObjError> {
+        let r = self.float(0, None)?;
+        Ok(Vec3::new(r, self.float(1, Some(r))?, self.float(2, Some(r))?))
+    }
+
+    fn rest(&self) -> Result<String, ObjError> {
+        match self.args.is_empty() {
+            true => Err(self.error(format!("'{}' needs a name", self.keyword))),
+            false => Ok(self.args.join(" ")),
+        }
+    }
+}
+
+// Yields the non-empty, comment-stripped statements of an OBJ or MTL file.
+fn statements<'a>(path: &'a Path, source: &'a str) -> impl Iterator<Item = Statement<'a>> {
+    source.lines().enumerate().filter_map(move |(index, text)| {
+        let text = text.split('#').next().unwrap_or("");
+        let mut fields = text.split_whitespace();
+        let keyword = fields.next()?;
+        Some(Statement { path, line: index + 1, keyword, args: fields.collect() })
+    })
+}
+
+struct MtlDef {
+    diffuse: Vec3,
+    specular: Vec3,
+    emission: Vec3,
+    transmission: Vec3,
+    shininess: f32,
+    ior: Option<f32>,
+    dissolve: f32,
+    illum: i32,
+}
+
+impl Default for MtlDef {
+    fn default() -> Self {
+        MtlDef {
+            diffuse: Vec3::new(0.8, 0.8, 0.8),
+            specular: Vec3::new(0.0, 0.0, 0.0),
+            emission: Vec3::new(0.0, 0.0, 0.0),
+            transmission: Vec3::new(1.0, 1.0, 1.0),
+            shininess: 0.0,
+            ior: None,
+            dissolve: 1.0,
+            illum: 2,
+        }
+    }
+}
+
+impl MtlDef {
+    // MTL describes Phong-style parameters; pick the closest of the renderer's materials.
+    fn build(&self) -> Box<dyn Material> {
+        if !self.emission.near_zero() {
+            Box::new(DiffuseLight::new(self.emission))
+        } else if self.dissolve < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9) {
+            Box::new(Dielectric::new(self.ior.unwrap_or(1.5), self.transmission))
+        } else if matches!(self.illum, 3 | 5) {
+            // Map the specular exponent (0..1000) onto fuzz, sharper highlights being less fuzzy.
+            let fuzz = (2.0 / (self.shininess.max(0.0) + 2.0)).sqrt();
+            // Ks defaults to black, which would make a mirror that reflects nothing.
+            let albedo = if self.specular.near_zero() { self.diffuse } else { self.specular };
+            Box::new(Metal::new(albedo, fuzz))
+        } else {
+            Box::new(Lambertian::new(self.diffuse))
+        }
+    }
+}
+
+fn load_mtl(path: &Path, defs: &mut HashMap<String, MtlDef>) -> Result<(), ObjError> {
+    let source = read(path)?;
+    parse_mtl(path, &source, defs)
+}
+
+fn parse_mtl(path: &Path, source: &str, defs: &mut HashMap<String, MtlDef>) -> Result<(), ObjError> {
+    let mut current: Option<String> = None;
+
+    for statement in statements(path, source) {
+        if statement.keyword == "newmtl" {
+            let name = statement.rest()?;
+            defs.insert(name.clone(), MtlDef::default());
+            current = Some(name);
+            continue;
+        }
+
+        let def = match current.as_ref().and_then(|name| defs.get_mut(name)) {
+            Some(def) => def,
+            None => return Err(statement.error(format!("'{}' before any 'newmtl'", statement.keyword))),
+        };
+        match statement.keyword {
+            "Kd" => def.diffuse = statement.color()?,
+            "Ks" => def.specular = statement.color()?,
+            "Ke" => def.emission = statement.color()?,
+            "Tf" => def.transmission = statement.color()?,
+            "Ns" => def.shininess = statement.float(0, None)?,
+            "Ni" => def.ior = Some(statement.float(0, None)?),
+            "d" => def.dissolve = statement.float(0, None)?,
+            "Tr" => def.dissolve = 1.0 - statement.float(0, None)?,
+            "illum" => def.illum = statement.float(0, None)? as i32,
+            // Texture maps and the remaining Phong terms have no counterpart here.
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// Resolves a 1-based, possibly negative (relative to the end) OBJ index.
+fn resolve(statement: &Statement, text: &str, count: usize) -> Result<usize, ObjError> {
+    let index = match text.parse::<i64>() {
+        Ok(index) => index,
+        Err(_) => return Err(statement.error(format!("invalid index '{}'", text))),
+    };
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(statement.error(format!("index {} is out of range (1..={})", index, count)));
+    }
+    Ok(resolved as usize)
+}
+
+// A face corner: position, then optional texture coordinate and normal ("v", "v/vt", "v//vn", "v/vt/vn").
+fn corner(statement: &Statement, text: &str, mesh: &Mesh) -> Result<(usize, Option<usize>, Option<usize>), ObjError> {
+    let mut parts = text.split('/');
+    let position = resolve(statement, parts.next().unwrap_or(""), mesh.positions.len())?;
+    let uv = match parts.next() {
+        Some(part) if !part.is_empty() => Some(resolve(statement, part, mesh.uvs.len())?),
+        _ => None,
+    };
+    let normal = match parts.next() {
+        Some(part) if !part.is_empty() => Some(resolve(statement, part, mesh.normals.len())?),
+        _ => None,
+    };
+    Ok((position, uv, normal))
+}
+
+// Faces that never select an MTL material use `default_material`.
+pub fn load(path: &Path, default_material: Box<dyn Material>) -> Result<Mesh, ObjError> {
+    let source = read(path)?;
+    parse(path, &source, default_material)
+}
+
+// MTL libraries are looked up next to `path`.
+fn parse(path: &Path, source: &str, default_material: Box<dyn Material>) -> Result<Mesh, ObjError> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    let mut mesh = Mesh::new();
+    let mut current = mesh.add_material(default_material);
+    let mut defs = HashMap::new();
+    let mut used: HashMap<String, usize> = HashMap::new();
+
+    for statement in statements(path, source) {
+        match statement.keyword {
+            "v" => mesh.positions.push(statement.vector()?),
+            "vn" => mesh.normals.push(statement.vector()?),
+            "vt" => mesh.uvs.push([statement.float(0, None)?, statement.float(1, Some(0.0))?]),
+            "f" => {
+                if statement.args.len() < 3 {
+                    return Err(statement.error("a face needs at least 3 vertices".to_string()));
+                }
+                let corners = statement
+                    .args
+                    .iter()
+                    .map(|text| corner(&statement, text, &mesh))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                // Triangulate polygons as a fan around the first corner.
+                for i in 1..corners.len() - 1 {
+                    let [a, b, c] = [corners[0], corners[i], corners[i + 1]];
+                    let uvs = match (a.1, b.1, c.1) {
+                        (Some(a), Some(b), Some(c)) => Some([a, b, c]),
+                        _ => None,
+                    };
+                    let normals = match (a.2, b.2, c.2) {
+                        (Some(a), Some(b), Some(c)) => Some([a, b, c]),
+                        _ => None,
+                    };
+                    mesh.add_face(Face { positions: [a.0, b.0, c.0], normals, uvs, material: current });
+                }
+            }
+            "mtllib" => {
+                for file in &statement.args {
+                    load_mtl(&dir.join(file), &mut defs)?;
+                }
+            }
+            "usemtl" => {
+                let name = statement.rest()?;
+                current = match used.get(&name) {
+                    Some(index) => *index,
+                    None => match defs.get(&name) {
+                        Some(def) => {
+                            let index = mesh.add_material(def.build());
+                            used.insert(name, index);
+                            index
+                        }
+                        None => return Err(statement.error(format!("unknown material '{}'", name))),
+                    },
+                };
+            }
+            // Groups, objects, smoothing groups, lines and points don't affect rendering.
+            _ => {}
+        }
+    }
+
+    if mesh.faces.is_empty() {
+        let line = source.lines().count();
+        return Err(ObjError::Parse { path: path.to_path_buf(), line, message: "mesh has no faces".to_string() });
+    }
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hits::HitRecord;
+    use crate::random;
+    use crate::ray::Ray;
+
+    const SQUARE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    fn gray() -> Box<dyn Material> {
+        Box::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))
+    }
+
+    fn mesh(source: &str) -> Mesh {
+        parse(Path::new("test.obj"), source, gray()).unwrap()
+    }
+
+    fn error(source: &str) -> (usize, String) {
+        match parse(Path::new("test.obj"), source, gray()) {
+            Err(ObjError::Parse { line, message, .. }) => (line, message),
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    fn positions(mesh: &Mesh) -> Vec<[usize; 3]> {
+        mesh.faces.iter().map(|face| face.positions).collect()
+    }
+
+    // A directory of its own holding `files`, for OBJ files that refer to MTL libraries.
+    fn fixture(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("raytrace-obj-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        for (file, contents) in files {
+            fs::write(dir.join(file), contents).unwrap();
+        }
+        dir
+    }
+
+    // What a material does with a ray hitting the z = 0 plane head on from above: the light
+    // it emits, and the attenuation and direction it scatters with.
+    fn probe(material: &dyn Material) -> (Vec3, Option<(Vec3, Vec3)>) {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let rec = HitRecord {
+            t: 1.0,
+            p: Vec3::new(0.0, 0.0, 0.0),
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            u: 0.0,
+            v: 0.0,
+            uv: None,
+            color: None,
+            material,
+        };
+        random::seed(1);
+        let scattered = material.scatter(&ray, &rec).map(|(attenuation, ray)| (attenuation, ray.direction().unit_vector()));
+        (material.emitted(), scattered)
+    }
+
+    #[test]
+    fn negative_indices_count_back_from_the_end() {
+        let mesh = mesh(&format!("{}vt 0 0\nvt 1 0\nvt 1 1\nf -4/-3 -3/-2 -2/-1\nv 2 2 0\nf 1 -2 -1", SQUARE));
+        assert_eq!(positions(&mesh), [[0, 1, 2], [0, 3, 4]]);
+        assert_eq!(mesh.faces[0].uvs, Some([0, 1, 2]));
+    }
+
+    #[test]
+    fn out_of_range_indices_are_errors() {
+        let source = |face: &str| format!("{}vt 0 0\nvn 0 0 1\n{}", SQUARE, face);
+        assert_eq!(error(&source("f 1 2 5")), (7, "index 5 is out of range (1..=4)".to_string()));
+        assert_eq!(error(&source("f 0 1 2")), (7, "index 0 is out of range (1..=4)".to_string()));
+        assert_eq!(error(&source("f -5 1 2")), (7, "index -5 is out of range (1..=4)".to_string()));
+        assert_eq!(error(&source("f 1/2 2/1 3/1")), (7, "index 2 is out of range (1..=1)".to_string()));
+        assert_eq!(error(&source("f 1//1 2//1 3//-2")), (7, "index -2 is out of range (1..=1)".to_string()));
+        assert_eq!(error(&source("f 1 2 x")), (7, "invalid index 'x'".to_string()));
+        assert_eq!(error(&source("f 1 2")), (7, "a face needs at least 3 vertices".to_string()));
+        // Indices only reach the vertices defined so far.
+        assert_eq!(error("v 0 0 0\nv 1 0 0\nf 1 2 3\nv 0 1 0"), (3, "index 3 is out of range (1..=2)".to_string()));
+    }
+
+    #[test]
+    fn polygons_become_fans() {
+        let mesh = mesh(&format!("{}v 0.5 1.5 0\nf 1 2 3 4\nf 1 2 3 5 4", SQUARE));
+        assert_eq!(positions(&mesh), [[0, 1, 2], [0, 2, 3], [0, 1, 2], [0, 2, 4], [0, 4, 3]]);
+    }
+
+    #[test]
+    fn corners_may_leave_out_texture_coordinates_or_normals() {
+        let attributes = "vt 0 0\nvt 1 0\nvt 1 1\nvn 0 0 1\nvn 0 1 0\nvn 1 0 0\n";
+        let mesh = mesh(&format!(
+            "{}{}f 1//1 2//2 3//3\nf 1/1 2/2 3/3\nf 1/1/1 2/2/2 3/3/3\nf 1//1 2/2 3/3/3",
+            SQUARE, attributes,
+        ));
+        let attributes: Vec<_> = mesh.faces.iter().map(|face| (face.uvs, face.normals)).collect();
+        assert_eq!(
+            attributes,
+            [
+                (None, Some([0, 1, 2])),
+                (Some([0, 1, 2]), None),
+                (Some([0, 1, 2]), Some([0, 1, 2])),
+                (None, None),
+            ],
+        );
+    }
+
+    #[test]
+    fn usemtl_needs_its_library_loaded_first() {
+        let dir = fixture("usemtl", &[("colors.mtl", "newmtl red\nKd 1 0 0\nnewmtl blue\nKd 0 0 1\n")]);
+        let path = dir.join("test.obj");
+
+        let before = format!("{}usemtl red\nmtllib colors.mtl\nf 1 2 3", SQUARE);
+        match parse(&path, &before, gray()) {
+            Err(ObjError::Parse { line, message, .. }) => assert_eq!((line, message.as_str()), (5, "unknown material 'red'")),
+            _ => panic!("expected an unknown material"),
+        }
+
+        let after = format!("{}f 1 2 3\nmtllib colors.mtl\nusemtl red\nf 1 2 3\nusemtl blue\nf 1 3 4\nusemtl red\nf 1 3 4", SQUARE);
+        let mesh = parse(&path, &after, gray()).unwrap();
+        let materials: Vec<usize> = mesh.faces.iter().map(|face| face.material).collect();
+        assert_eq!(materials, [0, 1, 2, 1]);
+        assert_eq!(mesh.materials.len(), 3);
+        assert_eq!(probe(mesh.materials[1].as_ref()).1.unwrap().0, Vec3::new(1.0, 0.0, 0.0));
+
+        match parse(&path, &format!("{}mtllib missing.mtl\nf 1 2 3", SQUARE), gray()) {
+            Err(ObjError::Io(missing, _)) => assert_eq!(missing, dir.join("missing.mtl")),
+            _ => panic!("expected a missing library"),
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn mtl_maps_onto_renderer_materials() {
+        let source = "\
+            newmtl plain\nKd 0.1 0.2 0.3\nKs 0.9 0.9 0.9\n\
+            newmtl lamp\nKd 0.1 0.2 0.3\nKe 4 4 4\n\
+            newmtl mirror\nKd 0.1 0.2 0.3\nKs 0.7 0.8 0.9\nNs 1000\nillum 3\n\
+            newmtl dark_mirror\nKd 0.1 0.2 0.3\nNs 1000\nillum 5\n\
+            newmtl glass\nKd 0.1 0.2 0.3\nTf 0.9 1 0.9\nNi 1.5\nillum 7\n\
+            newmtl veil\nKd 0.1 0.2 0.3\nd 0.5\n";
+        let mut defs = HashMap::new();
+        parse_mtl(Path::new("test.mtl"), source, &mut defs).unwrap();
+        let probe = |name: &str| probe(defs[name].build().as_ref());
+        let black = Vec3::new(0.0, 0.0, 0.0);
+        let diffuse = Vec3::new(0.1, 0.2, 0.3);
+        let up = Vec3::new(0.0, 0.0, 1.0);
+        let down = Vec3::new(0.0, 0.0, -1.0);
+
+        assert_eq!(probe("lamp"), (Vec3::new(4.0, 4.0, 4.0), None));
+
+        let (emitted, Some((albedo, _))) = probe("plain") else { panic!("plain does not scatter") };
+        assert_eq!((emitted, albedo), (black, diffuse));
+
+        // Mirrors bounce straight back, Ks tinting them or Kd standing in for a black Ks.
+        for (name, tint) in [("mirror", Vec3::new(0.7, 0.8, 0.9)), ("dark_mirror", diffuse)] {
+            let (emitted, Some((albedo, direction))) = probe(name) else { panic!("{} does not scatter", name) };
+            assert_eq!((emitted, albedo), (black, tint));
+            assert!(direction.dot(up) > 0.99);
+        }
+
+        // Glass carries Tf and mostly lets the ray through at normal incidence.
+        let (_, Some((albedo, direction))) = probe("glass") else { panic!("glass does not scatter") };
+        assert_eq!(albedo, Vec3::new(0.9, 1.0, 0.9));
+        assert!(direction.dot(down) > 0.99);
+        let (_, Some((albedo, _))) = probe("veil") else { panic!("veil does not scatter") };
+        assert_eq!(albedo, Vec3::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn mtl_statements_need_a_material() {
+        let mut defs = HashMap::new();
+        match parse_mtl(Path::new("test.mtl"), "# colors\nKd 1 0 0\n", &mut defs) {
+            Err(ObjError::Parse { line, message, .. }) => assert_eq!((line, message.as_str()), (2, "'Kd' before any 'newmtl'")),
+            _ => panic!("expected an error"),
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use crate::bounds::BBox;
+use crate::hits::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+pub struct Plane {
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub material: Box<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, material: Box<dyn Material>) -> Plane {
+        Plane { point, normal: normal.unit_vector(), material }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let denom = self.normal.dot(ray.direction());
+
+        // Rays parallel to the plane either miss it or slide along it; neither is a hit.
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.point - ray.origin()).dot(self.normal) / denom;
+        if t < t_max && t > t_min {
+            return Some(HitRecord {
+                t,
+                p: ray.at(t),
+                normal: self.normal,
+                u: 0.0,
+                v: 0.0,
+                uv: None,
+                color: None,
+                material: self.material.as_ref(),
+            });
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> BBox {
+        BBox::universe()
+    }
+}
@@ -0,0 +1,450 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::material::Material;
+use crate::mesh::{Face, Mesh};
+use crate::vec3::Vec3;
+
+#[derive(Debug)]
+pub enum PlyError {
+    Io(PathBuf, io::Error),
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for PlyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlyError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            PlyError::Invalid(path, message) => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl Error for PlyError {}
+
+#[derive(Copy, Clone)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn from_name(name: &str) -> Option<Scalar> {
+        match name {
+            "char" | "int8" => Some(Scalar::I8),
+            "uchar" | "uint8" => Some(Scalar::U8),
+            "short" | "int16" => Some(Scalar::I16),
+            "ushort" | "uint16" => Some(Scalar::U16),
+            "int" | "int32" => Some(Scalar::I32),
+            "uint" | "uint32" => Some(Scalar::U32),
+            "float" | "float32" => Some(Scalar::F32),
+            "double" | "float64" => Some(Scalar::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    // Integer color channels span their type's range; float channels are already in [0, 1].
+    fn color_scale(self) -> f64 {
+        match self {
+            Scalar::U8 => 255.0,
+            Scalar::U16 => 65535.0,
+            _ => 1.0,
+        }
+    }
+}
+
+enum PropertyKind {
+    Scalar(Scalar),
+    List(Scalar, Scalar),
+}
+
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn find(&self, name: &str) -> Option<usize> {
+        self.properties.iter().position(|property| property.name == name)
+    }
+}
+
+// Reads the values of the body one at a time, in either encoding.
+trait Values {
+    fn next(&mut self, scalar: Scalar) -> Result<f64, String>;
+}
+
+struct Ascii<'a> {
+    tokens: std::str::SplitAsciiWhitespace<'a>,
+}
+
+impl Values for Ascii<'_> {
+    fn next(&mut self, _scalar: Scalar) -> Result<f64, String> {
+        match self.tokens.next() {
+            Some(token) => token.parse().map_err(|_| format!("invalid number '{}'", token)),
+            None => Err("unexpected end of file".to_string()),
+        }
+    }
+}
+
+struct Binary<'a> {
+    bytes: &'a [u8],
+    big_endian: bool,
+}
+
+impl Values for Binary<'_> {
+    fn next(&mut self, scalar: Scalar) -> Result<f64, String> {
+        if self.bytes.len() < scalar.size() {
+            return Err("unexpected end of file".to_string());
+        }
+        let (value, rest) = self.bytes.split_at(scalar.size());
+        self.bytes = rest;
+
+        let mut b = [0u8; 8];
+        b[..value.len()].copy_from_slice(value);
+        if self.big_endian {
+            b[..value.len()].reverse();
+        }
+        Ok(match scalar {
+            Scalar::I8 => b[0] as i8 as f64,
+            Scalar::U8 => b[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Scalar::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Scalar::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Scalar::F64 => f64::from_le_bytes(b),
+        })
+    }
+}
+
+// Splits the file into its header lines and the start of the body.
+fn split_header(bytes: &[u8]) -> Result<(Vec<&str>, &[u8]), String> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+
+    while let Some(end) = bytes[start..].iter().position(|&b| b == b'\n') {
+        let line = std::str::from_utf8(&bytes[start..start + end]).map_err(|_| "header is not ASCII".to_string())?;
+        let line = line.trim_end_matches('\r');
+        start += end + 1;
+        if line.trim() == "end_header" {
+            return Ok((lines, &bytes[start..]));
+        }
+        lines.push(line);
+    }
+    Err("missing 'end_header'".to_string())
+}
+
+#[derive(Copy, Clone)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+fn parse_header(lines: &[&str]) -> Result<(Format, Vec<Element>), String> {
+    if lines.first().map(|line| line.trim()) != Some("ply") {
+        return Err("not a PLY file".to_string());
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+
+    for line in &lines[1..] {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["format", "ascii", _] => format = Some(Format::Ascii),
+            ["format", "binary_little_endian", _] => format = Some(Format::BinaryLittleEndian),
+            ["format", "binary_big_endian", _] => format = Some(Format::BinaryBigEndian),
+            ["format", format, _] => return Err(format!("unsupported format '{}'", format)),
+            ["element", name, count] => {
+                let count = count.parse().map_err(|_| format!("invalid element count '{}'", count))?;
+                elements.push(Element { name: name.to_string(), count, properties: Vec::new() });
+            }
+            ["property", "list", count, item, name] => {
+                let (count, item) = match (Scalar::from_name(count), Scalar::from_name(item)) {
+                    (Some(count), Some(item)) => (count, item),
+                    _ => return Err(format!("unknown type in '{}'", line.trim())),
+                };
+                match elements.last_mut() {
+                    Some(element) => element.properties.push(Property {
+                        name: name.to_string(),
+                        kind: PropertyKind::List(count, item),
+                    }),
+                    None => return Err("property before any element".to_string()),
+                }
+            }
+            ["property", scalar, name] => {
+                let scalar = Scalar::from_name(scalar).ok_or_else(|| format!("unknown type '{}'", scalar))?;
+                match elements.last_mut() {
+                    Some(element) => element.properties.push(Property {
+                        name: name.to_string(),
+                        kind: PropertyKind::Scalar(scalar),
+                    }),
+                    None => return Err("property before any element".to_string()),
+                }
+            }
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return Err(format!("malformed header line '{}'", line.trim())),
+        }
+    }
+
+    match format {
+        Some(format) => Ok((format, elements)),
+        None => Err("missing 'format' line".to_string()),
+    }
+}
+
+fn read_body(values: &mut dyn Values, elements: &[Element], mesh: &mut Mesh) -> Result<(), String> {
+    for element in elements {
+        let index = |name: &str| element.find(name);
+        let position = [index("x"), index("y"), index("z")];
+        let normal = [index("nx"), index("ny"), index("nz")];
+        let color = [index("red"), index("green"), index("blue")];
+        let faces = index("vertex_indices").or_else(|| index("vertex_index"));
+
+        if element.name == "vertex" && position.iter().any(Option::is_none) {
+            return Err("vertex element has no x, y, z properties".to_string());
+        }
+
+        let mut scalars = vec![0.0; element.properties.len()];
+        let mut list = Vec::new();
+
+        for _ in 0..element.count {
+            for (i, property) in element.properties.iter().enumerate() {
+                match property.kind {
+                    PropertyKind::Scalar(scalar) => scalars[i] = values.next(scalar)?,
+                    PropertyKind::List(count, item) => {
+                        let count = values.next(count)?;
+                        if count < 0.0 {
+                            return Err(format!("negative list length in '{}'", property.name));
+                        }
+                        let keep = element.name == "face" && Some(i) == faces;
+                        if keep {
+                            list.clear();
+                        }
+                        for _ in 0..count as usize {
+                            let value = values.next(item)?;
+                            if keep {
+                                list.push(value);
+                            }
+                        }
+                    }
+                }
+            }
+
+            match element.name.as_str() {
+                "vertex" => {
+                    let vector = |[x, y, z]: [Option<usize>; 3]| {
+                        Vec3::new(scalars[x.unwrap()] as f32, scalars[y.unwrap()] as f32, scalars[z.unwrap()] as f32)
+                    };
+                    mesh.positions.push(vector(position));
+                    if normal.iter().all(Option::is_some) {
+                        mesh.normals.push(vector(normal));
+                    }
+                    if color.iter().all(Option::is_some) {
+                        let PropertyKind::Scalar(scalar) = element.properties[color[0].unwrap()].kind else {
+                            return Err("color properties must be scalars".to_string());
+                        };
+                        mesh.colors.push(vector(color) / scalar.color_scale() as f32);
+                    }
+                }
+                "face" if faces.is_some() => {
+                    if list.len() < 3 {
+                        return Err("a face needs at least 3 vertices".to_string());
+                    }
+                    let count = mesh.positions.len();
+                    if let Some(bad) = list.iter().find(|&&i| i < 0.0 || i as usize >= count) {
+                        return Err(format!("vertex index {} is out of range (0..{})", bad, count));
+                    }
+                    // Triangulate polygons as a fan around the first corner.
+                    for i in 1..list.len() - 1 {
+                        let positions = [list[0] as usize, list[i] as usize, list[i + 1] as usize];
+                        let normals = if mesh.normals.is_empty() { None } else { Some(positions) };
+                        mesh.add_face(Face { positions, normals, uvs: None, material: 0 });
+                    }
+                }
+                // Edges, materials and other application-specific elements are skipped.
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+// Loads ASCII and binary PLY files. Per-vertex colors, when present, tint the albedo
+// of `material`.
+pub fn load(path: &Path, material: Box<dyn Material>) -> Result<Mesh, PlyError> {
+    let bytes = fs::read(path).map_err(|e| PlyError::Io(path.to_path_buf(), e))?;
+    parse(path, &bytes, material)
+}
+
+fn parse(path: &Path, bytes: &[u8], material: Box<dyn Material>) -> Result<Mesh, PlyError> {
+    let invalid = |message: String| PlyError::Invalid(path.to_path_buf(), message);
+
+    let (header, body) = split_header(bytes).map_err(invalid)?;
+    let (format, elements) = parse_header(&header).map_err(invalid)?;
+
+    // Faces may only refer to vertices that were already read.
+    let vertex = elements.iter().position(|element| element.name == "vertex");
+    let face = elements.iter().position(|element| element.name == "face");
+    match (vertex, face) {
+        (Some(vertex), Some(face)) if vertex < face => {}
+        (Some(_), Some(_)) => return Err(invalid("faces must come after vertices".to_string())),
+        _ => return Err(invalid("file needs both vertex and face elements".to_string())),
+    }
+
+    let mut mesh = Mesh::new();
+    mesh.add_material(material);
+
+    let result = match format {
+        Format::Ascii => {
+            let text = std::str::from_utf8(body).map_err(|_| invalid("body is not ASCII".to_string()))?;
+            read_body(&mut Ascii { tokens: text.split_ascii_whitespace() }, &elements, &mut mesh)
+        }
+        Format::BinaryLittleEndian => read_body(&mut Binary { bytes: body, big_endian: false }, &elements, &mut mesh),
+        Format::BinaryBigEndian => read_body(&mut Binary { bytes: body, big_endian: true }, &elements, &mut mesh),
+    };
+    result.map_err(invalid)?;
+
+    if mesh.faces.is_empty() {
+        return Err(invalid("mesh has no faces".to_string()));
+    }
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    const VERTICES: [([f32; 3], [u8; 3]); 4] = [
+        ([0.0, 0.0, 0.0], [255, 0, 0]),
+        ([1.0, 0.0, 0.0], [0, 255, 0]),
+        ([1.0, 1.0, 0.0], [0, 0, 255]),
+        ([0.0, 1.0, 0.5], [51, 102, 153]),
+    ];
+
+    fn header(format: &str) -> String {
+        format!(
+            "ply\nformat {} 1.0\ncomment a square\nelement vertex 4\nproperty float x\nproperty float y\n\
+             property float z\nproperty uchar red\nproperty uchar green\nproperty uchar blue\n\
+             element face 1\nproperty list uchar int vertex_indices\nend_header\n",
+            format,
+        )
+    }
+
+    fn ascii() -> Vec<u8> {
+        let mut text = header("ascii");
+        for ([x, y, z], [r, g, b]) in VERTICES {
+            text += &format!("{} {} {} {} {} {}\n", x, y, z, r, g, b);
+        }
+        text += "4 0 1 2 3\n";
+        text.into_bytes()
+    }
+
+    fn binary(big_endian: bool) -> Vec<u8> {
+        let format = if big_endian { "binary_big_endian" } else { "binary_little_endian" };
+        let mut bytes = header(format).into_bytes();
+        for (position, color) in VERTICES {
+            for value in position {
+                bytes.extend(if big_endian { value.to_be_bytes() } else { value.to_le_bytes() });
+            }
+            bytes.extend(color);
+        }
+        bytes.push(4);
+        for index in 0..4i32 {
+            bytes.extend(if big_endian { index.to_be_bytes() } else { index.to_le_bytes() });
+        }
+        bytes
+    }
+
+    fn mesh(bytes: &[u8]) -> Mesh {
+        parse(Path::new("test.ply"), bytes, Box::new(Lambertian::new(Vec3::new(1.0, 1.0, 1.0)))).unwrap()
+    }
+
+    fn error(bytes: &[u8]) -> String {
+        match parse(Path::new("test.ply"), bytes, Box::new(Lambertian::new(Vec3::new(1.0, 1.0, 1.0)))) {
+            Err(PlyError::Invalid(_, message)) => message,
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn every_encoding_reads_the_same_mesh() {
+        for bytes in [ascii(), binary(false), binary(true)] {
+            let mesh = mesh(&bytes);
+            let positions: Vec<Vec3> = VERTICES.iter().map(|&([x, y, z], _)| Vec3::new(x, y, z)).collect();
+            assert_eq!(mesh.positions, positions);
+            let faces: Vec<[usize; 3]> = mesh.faces.iter().map(|face| face.positions).collect();
+            assert_eq!(faces, [[0, 1, 2], [0, 2, 3]]);
+            assert!(mesh.normals.is_empty() && mesh.faces.iter().all(|face| face.normals.is_none()));
+        }
+    }
+
+    #[test]
+    fn vertex_colors_are_scaled_to_their_type() {
+        let colors = mesh(&ascii()).colors;
+        assert_eq!(colors[0], Vec3::new(1.0, 0.0, 0.0));
+        assert!((colors[3] - Vec3::new(0.2, 0.4, 0.6)).length() < 1e-6);
+
+        let source = |kind: &str, color: &str| {
+            format!(
+                "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+                 property {kind} red\nproperty {kind} green\nproperty {kind} blue\n\
+                 element face 1\nproperty list uchar uint vertex_index\nend_header\n\
+                 0 0 0 {color}\n1 0 0 {color}\n0 1 0 {color}\n3 0 1 2\n",
+            )
+        };
+        assert_eq!(mesh(source("ushort", "65535 0 13107").as_bytes()).colors[0], Vec3::new(1.0, 0.0, 0.2));
+        assert_eq!(mesh(source("float", "0.5 0.25 1").as_bytes()).colors[0], Vec3::new(0.5, 0.25, 1.0));
+
+        let plain = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+                     element face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n1 0 0\n0 1 0\n3 0 1 2\n";
+        assert!(mesh(plain.as_bytes()).colors.is_empty());
+    }
+
+    #[test]
+    fn truncated_bodies_are_errors() {
+        for (format, bytes) in [("ascii", ascii()), ("binary_little_endian", binary(false)), ("binary_big_endian", binary(true))] {
+            let body = header(format).len();
+            for cut in [body + 1, bytes.len() - 3] {
+                assert_eq!(error(&bytes[..cut]), "unexpected end of file");
+            }
+        }
+        assert_eq!(error(&ascii()[..20]), "missing 'end_header'");
+    }
+
+    #[test]
+    fn headers_and_indices_are_checked() {
+        let swap = |from: &str, to: &str| String::from_utf8(ascii()).unwrap().replacen(from, to, 1).into_bytes();
+        assert_eq!(error(&swap("ascii", "binary_middle_endian")), "unsupported format 'binary_middle_endian'");
+        assert_eq!(error(&swap("ply", "plx")), "not a PLY file");
+        assert_eq!(error(&swap("4 0 1 2 3", "4 0 1 2 4")), "vertex index 4 is out of range (0..4)");
+        assert_eq!(error(&swap("4 0 1 2 3", "2 0 1")), "a face needs at least 3 vertices");
+    }
+}
@@ -0,0 +1,166 @@
+use std::io::{self, Write};
+use crate::image::{quantize16, quantize8};
+use crate::vec3::Vec3;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+const COMPRESSION_LEVEL: u8 = 6;
+
+fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+        }
+        *entry = c;
+    }
+    table
+}
+
+fn crc32(table: &[u32; 256], parts: &[&[u8]]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for part in parts {
+        for &byte in part.iter() {
+            crc = table[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+        }
+    }
+    crc ^ 0xFFFF_FFFF
+}
+
+fn write_chunk<W: Write>(w: &mut W, table: &[u32; 256], kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+    w.write_all(&crc32(table, &[kind, data]).to_be_bytes())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+}
+
+// Applies all five PNG filters to a scanline and keeps the one with the smallest sum of
+// absolute residuals, the usual heuristic for photographic content.
+fn filter_row(row: &[u8], prev: &[u8], bpp: usize, out: &mut Vec<u8>) {
+    let mut best: Vec<u8> = Vec::new();
+    let mut best_score = u64::MAX;
+    let mut candidate = vec![0u8; row.len()];
+
+    for filter in 0..5u8 {
+        for i in 0..row.len() {
+            let a = if i >= bpp { row[i - bpp] } else { 0 };
+            let b = prev[i];
+            let c = if i >= bpp { prev[i - bpp] } else { 0 };
+            let predicted = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                _ => paeth(a, b, c),
+            };
+            candidate[i] = row[i].wrapping_sub(predicted);
+        }
+
+        let score: u64 = candidate.iter().map(|&v| (v as i8).unsigned_abs() as u64).sum();
+        if score < best_score {
+            best_score = score;
+            best.clear();
+            best.push(filter);
+            best.extend_from_slice(&candidate);
+        }
+    }
+
+    out.extend_from_slice(&best);
+}
+
+pub fn write_png<W: Write>(w: &mut W, width: usize, height: usize, pixels: &[Vec3], sixteen_bit: bool) -> io::Result<()> {
+    let table = crc32_table();
+    let bpp = if sixteen_bit { 6 } else { 3 };
+    let stride = width * bpp;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.push(if sixteen_bit { 16 } else { 8 });
+    header.extend_from_slice(&[2, 0, 0, 0]); // truecolor RGB, deflate, adaptive filtering, no interlace
+
+    let mut filtered = Vec::with_capacity(height * (stride + 1));
+    let mut prev = vec![0u8; stride];
+    let mut row = Vec::with_capacity(stride);
+
+    for y in 0..height {
+        row.clear();
+        for pixel in &pixels[y * width..(y + 1) * width] {
+            for channel in [pixel.r(), pixel.g(), pixel.b()] {
+                if sixteen_bit {
+                    row.extend_from_slice(&quantize16(channel).to_be_bytes());
+                } else {
+                    row.push(quantize8(channel));
+                }
+            }
+        }
+        filter_row(&row, &prev, bpp, &mut filtered);
+        std::mem::swap(&mut prev, &mut row);
+    }
+
+    let compressed = miniz_oxide::deflate::compress_to_vec_zlib(&filtered, COMPRESSION_LEVEL);
+
+    w.write_all(&SIGNATURE)?;
+    write_chunk(w, &table, b"IHDR", &header)?;
+    write_chunk(w, &table, b"sRGB", &[0])?; // perceptual rendering intent
+    write_chunk(w, &table, b"IDAT", &compressed)?;
+    write_chunk(w, &table, b"IEND", &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Splits a PNG into its chunks, checking each chunk's CRC along the way.
+    fn chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+        assert_eq!(png[..8], SIGNATURE);
+        let table = crc32_table();
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let kind: [u8; 4] = rest[4..8].try_into().unwrap();
+            let data = &rest[8..8 + len];
+            let crc = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+            assert_eq!(crc, crc32(&table, &[&kind, data]));
+            chunks.push((kind, data.to_vec()));
+            rest = &rest[12 + len..];
+        }
+        chunks
+    }
+
+    #[test]
+    fn crc_matches_known_values() {
+        let table = crc32_table();
+        assert_eq!(crc32(&table, &[b"123456789"]), 0xCBF4_3926);
+        assert_eq!(crc32(&table, &[b"1234", b"56789"]), 0xCBF4_3926);
+        assert_eq!(crc32(&table, &[b"IEND"]), 0xAE42_6082);
+    }
+
+    #[test]
+    fn one_pixel_round_trip() {
+        let pixel = [Vec3::new(1.0, 0.0, 0.5)];
+        for (sixteen_bit, row) in [
+            (false, vec![0, 255, 0, quantize8(0.5)]),
+            (true, [vec![0, 255, 255, 0, 0], quantize16(0.5).to_be_bytes().to_vec()].concat()),
+        ] {
+            let mut png = Vec::new();
+            write_png(&mut png, 1, 1, &pixel, sixteen_bit).unwrap();
+
+            let chunks = chunks(&png);
+            let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(kind, _)| kind).collect();
+            assert_eq!(kinds, [b"IHDR", b"sRGB", b"IDAT", b"IEND"]);
+            let depth = if sixteen_bit { 16 } else { 8 };
+            assert_eq!(chunks[0].1, [0, 0, 0, 1, 0, 0, 0, 1, depth, 2, 0, 0, 0]);
+            assert_eq!(miniz_oxide::inflate::decompress_to_vec_zlib(&chunks[2].1).unwrap(), row);
+        }
+    }
+}
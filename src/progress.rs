@@ -0,0 +1,184 @@
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+use crate::scheduler::Tile;
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ProgressFormat {
+    Human,
+    Json,
+    Off,
+}
+
+impl ProgressFormat {
+    pub fn from_name(name: &str) -> Option<ProgressFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "human" => Some(ProgressFormat::Human),
+            "json" => Some(ProgressFormat::Json),
+            "off" | "none" => Some(ProgressFormat::Off),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+struct WorkerStatus {
+    tile: Option<usize>,
+    tiles_done: usize,
+}
+
+// Collects tile events from the render threads and periodically reports to stderr.
+pub struct Progress {
+    format: ProgressFormat,
+    start: Instant,
+    last_report: Instant,
+    total_tiles: usize,
+    tiles_done: usize,
+    total_pixels: usize,
+    pixels_done: usize,
+    rays: u64,
+    workers: Vec<WorkerStatus>,
+    overwrite: bool,
+}
+
+fn hms(seconds: f64) -> String {
+    let s = seconds.max(0.0).round() as u64;
+    format!("{:02}:{:02}:{:02}", s / 3600, (s / 60) % 60, s % 60)
+}
+
+fn rate(rays_per_second: f64) -> String {
+    if rays_per_second >= 1e6 {
+        format!("{:.2} Mrays/s", rays_per_second / 1e6)
+    } else {
+        format!("{:.1} krays/s", rays_per_second / 1e3)
+    }
+}
+
+impl Progress {
+    pub fn new(format: ProgressFormat, total_tiles: usize, total_pixels: usize, workers: usize) -> Progress {
+        let now = Instant::now();
+        Progress {
+            format,
+            start: now,
+            last_report: now,
+            total_tiles,
+            tiles_done: 0,
+            total_pixels,
+            pixels_done: 0,
+            rays: 0,
+            workers: vec![WorkerStatus::default(); workers],
+            overwrite: io::stderr().is_terminal(),
+        }
+    }
+
+    pub fn started(&mut self) {
+        match self.format {
+            ProgressFormat::Human => {
+                eprintln!("Rendering {} tiles with {} threads...", self.total_tiles, self.workers.len());
+            }
+            ProgressFormat::Json => {
+                eprintln!(
+                    "{{\"event\":\"start\",\"tiles_total\":{},\"pixels_total\":{},\"threads\":{}}}",
+                    self.total_tiles, self.total_pixels, self.workers.len()
+                );
+            }
+            ProgressFormat::Off => {}
+        }
+    }
+
+    pub fn tile_started(&mut self, worker: usize, tile: &Tile) {
+        self.workers[worker].tile = Some(tile.index);
+    }
+
+    pub fn tile_finished(&mut self, worker: usize, tile: &Tile, rays: u64) {
+        let status = &mut self.workers[worker];
+        status.tile = None;
+        status.tiles_done += 1;
+
+        self.tiles_done += 1;
+        self.pixels_done += tile.pixel_count();
+        self.rays += rays;
+    }
+
+    pub fn report_if_due(&mut self) {
+        if self.last_report.elapsed() >= REPORT_INTERVAL {
+            self.report("progress");
+        }
+    }
+
+    pub fn finish(&mut self) {
+        self.report("done");
+        if self.format == ProgressFormat::Human && self.overwrite {
+            eprintln!();
+        }
+    }
+
+    fn report(&mut self, event: &str) {
+        self.last_report = Instant::now();
+
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let percent = 100.0 * self.tiles_done as f64 / self.total_tiles.max(1) as f64;
+        let rays_per_second = if elapsed > 0.0 { self.rays as f64 / elapsed } else { 0.0 };
+        // Edge tiles are smaller than the rest, so estimate from pixels rather than tiles.
+        let eta = if self.pixels_done > 0 {
+            Some(elapsed * (self.total_pixels - self.pixels_done) as f64 / self.pixels_done as f64)
+        } else {
+            None
+        };
+
+        match self.format {
+            ProgressFormat::Human => {
+                let threads: Vec<String> = self
+                    .workers
+                    .iter()
+                    .map(|w| w.tile.map_or("-".to_string(), |tile| tile.to_string()))
+                    .collect();
+                let line = format!(
+                    "[{:5.1}%] {}/{} tiles | {} elapsed | ETA {} | {} | tiles in flight: {}",
+                    percent,
+                    self.tiles_done,
+                    self.total_tiles,
+                    hms(elapsed),
+                    eta.map_or("--:--:--".to_string(), hms),
+                    rate(rays_per_second),
+                    threads.join(" ")
+                );
+                if self.overwrite {
+                    eprint!("\r\x1b[K{}", line);
+                    io::stderr().flush().ok();
+                } else {
+                    eprintln!("{}", line);
+                }
+            }
+            ProgressFormat::Json => {
+                let threads: Vec<String> = self
+                    .workers
+                    .iter()
+                    .enumerate()
+                    .map(|(id, w)| {
+                        format!(
+                            "{{\"id\":{},\"tile\":{},\"tiles_done\":{}}}",
+                            id,
+                            w.tile.map_or("null".to_string(), |tile| tile.to_string()),
+                            w.tiles_done
+                        )
+                    })
+                    .collect();
+                eprintln!(
+                    "{{\"event\":\"{}\",\"tiles_done\":{},\"tiles_total\":{},\"percent\":{:.2},\"elapsed_s\":{:.3},\"eta_s\":{},\"rays\":{},\"rays_per_s\":{:.0},\"threads\":[{}]}}",
+                    event,
+                    self.tiles_done,
+                    self.total_tiles,
+                    percent,
+                    elapsed,
+                    eta.map_or("null".to_string(), |eta| format!("{:.3}", eta)),
+                    self.rays,
+                    rays_per_second,
+                    threads.join(",")
+                );
+            }
+            ProgressFormat::Off => {}
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::bounds::BBox;
+use crate::hits::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// The parallelogram spanned by `u` and `v` from the corner `q`, shared by `Quad` and `Cuboid`.
+#[derive(Copy, Clone)]
+pub struct Parallelogram {
+    pub q: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+    // Unit normal along u x v, and the plane offset along it.
+    pub normal: Vec3,
+    d: f32,
+    // Maps a point in the plane back onto the (u, v) basis.
+    w: Vec3,
+}
+
+impl Parallelogram {
+    pub fn new(q: Vec3, u: Vec3, v: Vec3) -> Parallelogram {
+        let n = u.cross(v);
+        let normal = n.unit_vector();
+        Parallelogram { q, u, v, normal, d: normal.dot(q), w: n / n.dot(n) }
+    }
+
+    // Returns the distance along the ray and the hit's coordinates along `u` and `v`, both in [0, 1].
+    pub fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<(f32, f32, f32)> {
+        let denom = self.normal.dot(ray.direction());
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - self.normal.dot(ray.origin())) / denom;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let planar = ray.at(t) - self.q;
+        let alpha = self.w.dot(planar.cross(self.v));
+        let beta = self.w.dot(self.u.cross(planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+        Some((t, alpha, beta))
+    }
+
+    pub fn bounding_box(&self) -> BBox {
+        let diagonal1 = BBox::from_points(self.q, self.q + self.u + self.v);
+        let diagonal2 = BBox::from_points(self.q + self.u, self.q + self.v);
+        BBox::surrounding(&diagonal1, &diagonal2).pad()
+    }
+}
+
+pub struct Quad {
+    pub shape: Parallelogram,
+    pub material: Box<dyn Material>,
+}
+
+impl Quad {
+    pub fn new(q: Vec3, u: Vec3, v: Vec3, material: Box<dyn Material>) -> Quad {
+        Quad { shape: Parallelogram::new(q, u, v), material }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let (t, u, v) = self.shape.intersect(ray, t_min, t_max)?;
+        Some(HitRecord {
+            t,
+            p: ray.at(t),
+            normal: self.shape.normal,
+            u,
+            v,
+            uv: None,
+            color: None,
+            material: self.material.as_ref(),
+        })
+    }
+
+    fn bounding_box(&self) -> BBox {
+        self.shape.bounding_box()
+    }
+}
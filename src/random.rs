@@ -0,0 +1,29 @@
+use std::cell::RefCell;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// Every random draw in the renderer goes through this per-thread generator so a
+// render can be reproduced by seeding it.
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::seed_from_u64(rand::rng().random()));
+}
+
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+pub fn random_f32() -> f32 {
+    RNG.with(|rng| rng.borrow_mut().random())
+}
+
+pub fn random_range(min: f32, max: f32) -> f32 {
+    RNG.with(|rng| rng.borrow_mut().random_range(min..max))
+}
+
+// Derives an independent stream seed for one unit of work (a thread, a tile...).
+pub fn derive_seed(seed: u64, stream: u64) -> u64 {
+    let mut z = seed ^ stream.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -4,15 +4,22 @@ use crate::vec3::Vec3;
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
+    // The moment within the camera's shutter interval the ray samples, for moving objects.
+    pub time: f32,
 }
 
 impl Ray {
     pub fn new(origin: Vec3, direction: Vec3) -> Ray {
-        Ray { origin, direction, }
+        Ray { origin, direction, time: 0.0 }
+    }
+
+    pub fn with_time(origin: Vec3, direction: Vec3, time: f32) -> Ray {
+        Ray { time, ..Ray::new(origin, direction) }
     }
 
     pub fn origin(&self) -> Vec3 { self.origin }
     pub fn direction(&self) -> Vec3 { self.direction }
+    pub fn time(&self) -> f32 { self.time }
 
     pub fn at(&self, t: f32) -> Vec3 {
         self.origin + self.direction * t
@@ -1,10 +1,17 @@
-use std::io::Write;
-use std::sync::{Arc, Mutex};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
 use std::thread;
-use rand::{rng, Rng};
-use crate::camera::Camera;
-use crate::color::color;
+use std::time::Duration;
+use crate::camera::{Camera, CameraSettings};
+use crate::color::{color, rays_traced};
+use crate::framebuffer::Framebuffer;
 use crate::hits::HittableList;
+use crate::image::ImageFormat;
+use crate::progress::{Progress, ProgressFormat};
+use crate::random;
+use crate::scheduler::{self, Tile, TileOrder, WorkQueues};
 use crate::vec3::Vec3;
 
 pub struct Render {
@@ -18,6 +25,21 @@ pub struct Render {
     ipd: f32,
     aperture: f32,
     focus_dist: f32,
+    shutter: (f32, f32),
+    background: Option<Vec3>,
+    max_depth: i32,
+    threads: Option<usize>,
+    seed: Option<u64>,
+    output: PathBuf,
+    format: Option<ImageFormat>,
+    tile_size: usize,
+    tile_order: TileOrder,
+    progress: ProgressFormat,
+}
+
+enum TileEvent {
+    Started(usize, Tile),
+    Finished(usize, Tile, Vec<Vec3>, u64),
 }
 
 impl Render {
@@ -33,6 +55,16 @@ impl Render {
             ipd: 0.06,
             aperture: 0.0,
             focus_dist: 10.0,
+            shutter: (0.0, 0.0),
+            background: None,
+            max_depth: 50,
+            threads: None,
+            seed: None,
+            output: PathBuf::from("out.ppm"),
+            format: None,
+            tile_size: 32,
+            tile_order: TileOrder::Spiral,
+            progress: ProgressFormat::Human,
         }
     }
 
@@ -71,94 +103,199 @@ impl Render {
         self
     }
 
-    pub fn render_scene(&self, world: HittableList, anaglyph: bool) {
-        let mut file = std::fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open("out.ppm")
-            .unwrap();
-
-        writeln!(file, "P3\n{} {}\n255", self.nx, self.ny).unwrap();
-
-        let world = Arc::new(world);
-        let camera = Arc::new(Camera::new(
-            self.lookfrom,
-            self.lookat,
-            self.vup,
-            self.vfov,
-            self.nx as f32 / self.ny as f32,
-            self.ipd,
-            self.aperture,
-            self.focus_dist
-        ));
-
-        let num_threads = std::thread::available_parallelism().unwrap().get();
-        let rows_per_thread = self.ny / num_threads as i32;
-        let mut handles = vec![];
-
-        eprintln!("Rendering with {} threads...", num_threads);
-
-        for t in 0..num_threads {
-            let world = Arc::clone(&world);
-            let camera = Arc::clone(&camera);
-            let nx = self.nx;
-            let ny = self.ny;
-            let ns = self.ns;
-
-            let start_y = t as i32 * rows_per_thread;
-            let end_y = if t == num_threads - 1 { ny } else { (t + 1) as i32 * rows_per_thread };
-
-            handles.push(thread::spawn(move || {
-                let mut buffer = Vec::new();
-                let mut rng = rng();
-
-                for j in (start_y..end_y).rev() {
-                    for i in 0..nx {
-                        let mut col_left = Vec3::new(0.0, 0.0, 0.0);
-                        let mut col_right = Vec3::new(0.0, 0.0, 0.0);
-
-                        for _ in 0..ns {
-                            let u = (i as f32 + rng.random_range(0f32..1f32)) / nx as f32;
-                            let v = (j as f32 + rng.random_range(0f32..1f32)) / ny as f32;
-
-                            if anaglyph {
-                                let r_left = camera.left_eye.get_ray(u, v);
-                                col_left += color(&r_left, &world, 50);
-                            }
+    // The span of time each pixel integrates over; objects moving during it are blurred.
+    pub fn shutter(&mut self, open: f32, close: f32) -> &mut Self {
+        self.shutter = (open, close);
+        self
+    }
 
-                            let r_right = camera.right_eye.get_ray(u, v);
-                            col_right += color(&r_right, &world, 50);
-                        }
+    pub fn background(&mut self, r: f32, g: f32, b: f32) -> &mut Self {
+        self.background = Some(Vec3::new(r, g, b));
+        self
+    }
 
-                        if anaglyph { col_left /= ns as f32; }
-                        col_right /= ns as f32;
+    pub fn max_depth(&mut self, max_depth: i32) -> &mut Self {
+        self.max_depth = max_depth;
+        self
+    }
 
-                        if anaglyph { col_left = Vec3::new(col_left.x.sqrt(), col_left.y.sqrt(), col_left.z.sqrt()); }
-                        col_right = Vec3::new(col_right.x.sqrt(), col_right.y.sqrt(), col_right.z.sqrt());
+    pub fn threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = Some(threads);
+        self
+    }
 
-                        let ir = (255.99 * if anaglyph { col_left.r() } else { col_right.r() }) as i32;
-                        let ig = (255.99 * col_right.g()) as i32;
-                        let ib = (255.99 * col_right.b()) as i32;
+    pub fn seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = Some(seed);
+        self
+    }
 
-                        buffer.push(format!("{} {} {}", ir, ig, ib));
-                    }
-                }
-                buffer
-            }));
+    pub fn output(&mut self, path: &Path) -> &mut Self {
+        self.output = path.to_path_buf();
+        self
+    }
+
+    // Without an explicit format the output path's extension decides, falling back to PPM.
+    pub fn format(&mut self, format: ImageFormat) -> &mut Self {
+        self.format = Some(format);
+        self
+    }
+
+    pub fn tile_size(&mut self, tile_size: usize) -> &mut Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    pub fn tile_order(&mut self, tile_order: TileOrder) -> &mut Self {
+        self.tile_order = tile_order;
+        self
+    }
+
+    pub fn progress(&mut self, progress: ProgressFormat) -> &mut Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        self.nx as f32 / self.ny as f32
+    }
+
+    pub fn render_scene(&self, world: HittableList, anaglyph: bool) -> io::Result<()> {
+        // "-" streams the image to stdout so it can be piped into other tools, as PPM unless
+        // told otherwise; files must name their format, so a typo doesn't silently write PPM.
+        let stdout = self.output.as_os_str() == "-";
+        let format = match self.format.or_else(|| ImageFormat::from_path(&self.output)) {
+            Some(format) => format,
+            None if stdout => ImageFormat::Ppm,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "unknown image format (expected a .ppm, .png, .exr or .hdr extension, or --format)",
+                ))
+            }
+        };
+        let mut out: Box<dyn Write> = if stdout {
+            Box::new(BufWriter::new(io::stdout().lock()))
+        } else {
+            Box::new(BufWriter::new(File::create(&self.output)?))
+        };
+
+        let framebuffer = self.render(world, anaglyph);
+        framebuffer.write(&mut out, format)?;
+
+        if self.progress == ProgressFormat::Human {
+            eprintln!("Done.");
         }
+        Ok(())
+    }
+
+    fn sample_pixel(&self, camera: &Camera, world: &HittableList, x: usize, y: usize, anaglyph: bool) -> Vec3 {
+        let mut col_left = Vec3::new(0.0, 0.0, 0.0);
+        let mut col_right = Vec3::new(0.0, 0.0, 0.0);
+
+        // The camera's v axis points up while framebuffer rows count down from the top.
+        let j = self.ny as usize - 1 - y;
+
+        for _ in 0..self.ns {
+            let u = (x as f32 + random::random_f32()) / self.nx as f32;
+            let v = (j as f32 + random::random_f32()) / self.ny as f32;
+
+            if anaglyph {
+                let r_left = camera.left_eye.get_ray(u, v);
+                col_left += color(&r_left, world, self.max_depth, self.background);
+            }
 
-        let mut results = Vec::new();
-        for handle in handles {
-            results.push(handle.join().unwrap());
+            let r_right = camera.right_eye.get_ray(u, v);
+            col_right += color(&r_right, world, self.max_depth, self.background);
         }
 
-        for buffer in results.iter().rev() {
-            for line in buffer {
-                writeln!(file, "{}", line).unwrap();
+        if anaglyph { col_left /= self.ns as f32; }
+        col_right /= self.ns as f32;
+
+        if anaglyph { col_right.x = col_left.r(); }
+
+        col_right
+    }
+
+    fn render_tile(&self, camera: &Camera, world: &HittableList, tile: &Tile, anaglyph: bool) -> Vec<Vec3> {
+        if let Some(seed) = self.seed {
+            random::seed(random::derive_seed(seed, tile.index as u64));
+        }
+
+        let mut pixels = Vec::with_capacity(tile.pixel_count());
+        for y in tile.y0..tile.y1 {
+            for x in tile.x0..tile.x1 {
+                pixels.push(self.sample_pixel(camera, world, x, y, anaglyph));
             }
         }
+        pixels
+    }
+
+    pub fn render(&self, world: HittableList, anaglyph: bool) -> Framebuffer {
+        let camera = Camera::new(&CameraSettings {
+            lookfrom: self.lookfrom,
+            lookat: self.lookat,
+            vup: self.vup,
+            vfov: self.vfov,
+            aspect: self.nx as f32 / self.ny as f32,
+            ipd: self.ipd,
+            defocus_angle: self.aperture,
+            focus_dist: self.focus_dist,
+            shutter: self.shutter,
+        });
+
+        let (width, height) = (self.nx as usize, self.ny as usize);
+        let tiles = scheduler::tiles(width, height, self.tile_size, self.tile_order);
+
+        let num_threads = self.threads.unwrap_or_else(|| std::thread::available_parallelism().unwrap().get());
+        let mut progress = Progress::new(self.progress, tiles.len(), width * height, num_threads);
+        let queues = WorkQueues::new(tiles, num_threads);
+        let mut framebuffer = Framebuffer::new(width, height);
+
+        progress.started();
+
+        thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel();
+
+            for worker in 0..num_threads {
+                let sender = sender.clone();
+                let (camera, world, queues) = (&camera, &world, &queues);
+
+                scope.spawn(move || {
+                    while let Some(tile) = queues.next(worker) {
+                        if sender.send(TileEvent::Started(worker, tile)).is_err() {
+                            break;
+                        }
+                        let rays_before = rays_traced();
+                        let pixels = self.render_tile(camera, world, &tile, anaglyph);
+                        let rays = rays_traced() - rays_before;
+                        if sender.send(TileEvent::Finished(worker, tile, pixels, rays)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+
+            loop {
+                match receiver.recv_timeout(Duration::from_millis(100)) {
+                    Ok(TileEvent::Started(worker, tile)) => progress.tile_started(worker, &tile),
+                    Ok(TileEvent::Finished(worker, tile, pixels, rays)) => {
+                        let mut pixels = pixels.into_iter();
+                        for y in tile.y0..tile.y1 {
+                            for x in tile.x0..tile.x1 {
+                                framebuffer.set(x, y, pixels.next().unwrap());
+                            }
+                        }
+                        progress.tile_finished(worker, &tile, rays);
+                    }
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                }
+                progress.report_if_due();
+            }
+        });
+        progress.finish();
 
-        eprintln!("\nDone.");
+        framebuffer
     }
 }
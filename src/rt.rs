@@ -0,0 +1,408 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::hits::HittableList;
+use crate::cylinder::Cylinder;
+use crate::material::{DiffuseLight, Lambertian};
+use crate::plane::Plane;
+use crate::render::Render;
+use crate::scene::Scene;
+use crate::sphere::Sphere;
+use crate::vec3::Vec3;
+
+// The path tracer has no explicit light sampling, so point lights are turned into
+// small emissive spheres that diffuse bounces can find. This is an approximation: the
+// spheres are solid, so camera rays see them as glowing balls and they cast shadows
+// like any other object, and the light falls off from a radius-1 source, not a point.
+const LIGHT_RADIUS: f32 = 1.0;
+const LIGHT_POWER: f32 = 100.0;
+
+#[derive(Debug)]
+pub enum RtError {
+    Io(io::Error),
+    Parse { line: usize, column: usize, message: String },
+}
+
+impl fmt::Display for RtError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RtError::Io(e) => write!(f, "{}", e),
+            RtError::Parse { line, column, message } => {
+                write!(f, "line {}, column {}: {}", line, column, message)
+            }
+        }
+    }
+}
+
+impl Error for RtError {}
+
+impl From<io::Error> for RtError {
+    fn from(e: io::Error) -> Self {
+        RtError::Io(e)
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Field<'a> {
+    text: &'a str,
+    column: usize,
+}
+
+struct Line<'a> {
+    number: usize,
+    fields: Vec<Field<'a>>,
+    next: usize,
+    end_column: usize,
+}
+
+impl<'a> Line<'a> {
+    fn new(number: usize, text: &'a str) -> Line<'a> {
+        let mut fields = Vec::new();
+        let mut start = None;
+
+        for (column, (offset, c)) in text.char_indices().enumerate() {
+            match (c.is_whitespace(), start) {
+                (false, None) => start = Some((offset, column + 1)),
+                (true, Some((begin, col))) => {
+                    fields.push(Field { text: &text[begin..offset], column: col });
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some((begin, col)) = start {
+            fields.push(Field { text: &text[begin..], column: col });
+        }
+
+        Line { number, fields, next: 0, end_column: text.chars().count() + 1 }
+    }
+
+    fn error(&self, column: usize, message: String) -> RtError {
+        RtError::Parse { line: self.number, column, message }
+    }
+
+    fn next_field(&mut self, what: &str) -> Result<Field<'a>, RtError> {
+        match self.fields.get(self.next) {
+            Some(field) => {
+                self.next += 1;
+                Ok(*field)
+            }
+            None => Err(self.error(self.end_column, format!("missing {}", what))),
+        }
+    }
+
+    fn finish(&self) -> Result<(), RtError> {
+        match self.fields.get(self.next) {
+            Some(field) => Err(self.error(field.column, format!("unexpected '{}'", field.text))),
+            None => Ok(()),
+        }
+    }
+
+    fn number(&self, field: Field, what: &str) -> Result<f32, RtError> {
+        match field.text.parse::<f32>() {
+            Ok(value) if value.is_finite() => Ok(value),
+            _ => Err(self.error(field.column, format!("invalid {} '{}'", what, field.text))),
+        }
+    }
+
+    fn float_in(&mut self, what: &str, min: f32, max: f32) -> Result<f32, RtError> {
+        let field = self.next_field(what)?;
+        let value = self.number(field, what)?;
+        if value < min || value > max {
+            return Err(self.error(field.column, format!("{} {} is out of range [{}, {}]", what, value, min, max)));
+        }
+        Ok(value)
+    }
+
+    fn positive(&mut self, what: &str) -> Result<f32, RtError> {
+        let field = self.next_field(what)?;
+        let value = self.number(field, what)?;
+        if value <= 0.0 {
+            return Err(self.error(field.column, format!("{} must be positive, got {}", what, value)));
+        }
+        Ok(value)
+    }
+
+    fn components(&self, field: Field<'a>, what: &str) -> Result<[Field<'a>; 3], RtError> {
+        let mut parts = [field; 3];
+        let mut count = 0;
+        let mut column = field.column;
+
+        for text in field.text.split(',') {
+            if count == 3 {
+                return Err(self.error(field.column, format!("{} '{}' must have 3 components", what, field.text)));
+            }
+            parts[count] = Field { text, column };
+            column += text.chars().count() + 1;
+            count += 1;
+        }
+        if count != 3 {
+            return Err(self.error(field.column, format!("{} '{}' must have 3 components", what, field.text)));
+        }
+        Ok(parts)
+    }
+
+    fn vector(&mut self, what: &str) -> Result<Vec3, RtError> {
+        let field = self.next_field(what)?;
+        let [x, y, z] = self.components(field, what)?;
+        Ok(Vec3::new(self.number(x, what)?, self.number(y, what)?, self.number(z, what)?))
+    }
+
+    fn direction(&mut self, what: &str) -> Result<Vec3, RtError> {
+        let field = self.next_field(what)?;
+        let parts = self.components(field, what)?;
+        let mut v = Vec3::new(0.0, 0.0, 0.0);
+
+        for (axis, part) in parts.iter().enumerate() {
+            let value = self.number(*part, what)?;
+            if !(-1.0..=1.0).contains(&value) {
+                return Err(self.error(part.column, format!("{} component {} is out of range [-1, 1]", what, value)));
+            }
+            v[axis] = value;
+        }
+        if v.near_zero() {
+            return Err(self.error(field.column, format!("{} must not be the zero vector", what)));
+        }
+        Ok(v.unit_vector())
+    }
+
+    fn color(&mut self, what: &str) -> Result<Vec3, RtError> {
+        let field = self.next_field(what)?;
+        let parts = self.components(field, what)?;
+        let mut c = Vec3::new(0.0, 0.0, 0.0);
+
+        for (channel, part) in parts.iter().enumerate() {
+            match part.text.parse::<i32>() {
+                Ok(value) if (0..=255).contains(&value) => c[channel] = value as f32 / 255.0,
+                Ok(value) => {
+                    return Err(self.error(part.column, format!("{} channel {} is out of range [0, 255]", what, value)));
+                }
+                Err(_) => return Err(self.error(part.column, format!("invalid {} channel '{}'", what, part.text))),
+            }
+        }
+        Ok(c)
+    }
+}
+
+// Uppercase elements may only appear once per scene.
+fn declare(seen: &mut Option<usize>, line: &Line, id: Field) -> Result<(), RtError> {
+    if let Some(first) = seen {
+        return Err(line.error(id.column, format!("duplicate '{}' element (first declared on line {})", id.text, first)));
+    }
+    *seen = Some(line.number);
+    Ok(())
+}
+
+// miniRT specifies a horizontal field of view, the camera expects a vertical one.
+fn vertical_fov(horizontal: f32, aspect: f32) -> f32 {
+    let half_width = (horizontal.to_radians() / 2.0).tan();
+    2.0 * (half_width / aspect).atan().to_degrees()
+}
+
+pub fn load(path: &Path, render: Render) -> Result<Scene, RtError> {
+    let source = fs::read_to_string(path)?;
+    parse(&source, render)
+}
+
+pub fn parse(source: &str, mut render: Render) -> Result<Scene, RtError> {
+    let mut world = HittableList::new();
+    let mut ambient = None;
+    let mut camera = None;
+    let mut light = None;
+    let mut line_count = 0;
+
+    for (index, text) in source.lines().enumerate() {
+        line_count = index + 1;
+        let mut line = Line::new(index + 1, text);
+        let id = match line.fields.first() {
+            Some(id) if !id.text.starts_with('#') => *id,
+            _ => continue,
+        };
+        line.next = 1;
+
+        match id.text {
+            "A" => {
+                declare(&mut ambient, &line, id)?;
+                let ratio = line.float_in("ambient ratio", 0.0, 1.0)?;
+                let color = line.color("ambient color")?;
+                line.finish()?;
+
+                let background = color * ratio;
+                render.background(background.x, background.y, background.z);
+            }
+            "C" => {
+                declare(&mut camera, &line, id)?;
+                let position = line.vector("camera position")?;
+                let orientation = line.direction("camera orientation")?;
+                let fov_field = line.next_field("field of view")?;
+                let fov = line.number(fov_field, "field of view")?;
+                if fov <= 0.0 || fov >= 180.0 {
+                    return Err(line.error(fov_field.column, format!("field of view {} is out of range (0, 180)", fov)));
+                }
+                line.finish()?;
+
+                let vfov = vertical_fov(fov, render.aspect_ratio());
+                let vup = if orientation.y.abs() > 0.999 { Vec3::new(0.0, 0.0, 1.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+                let lookat = position + orientation;
+
+                render
+                    .lookfrom(position.x, position.y, position.z)
+                    .lookat(lookat.x, lookat.y, lookat.z)
+                    .vup(vup.x, vup.y, vup.z)
+                    .vfov(vfov)
+                    .aperture(0.0);
+            }
+            "L" => {
+                declare(&mut light, &line, id)?;
+                let position = line.vector("light position")?;
+                let brightness = line.float_in("light brightness", 0.0, 1.0)?;
+                let color = line.color("light color")?;
+                line.finish()?;
+
+                let material = Box::new(DiffuseLight::new(color * brightness * LIGHT_POWER));
+                world.add(Box::new(Sphere::new(position, LIGHT_RADIUS, material)));
+            }
+            "sp" => {
+                let center = line.vector("sphere center")?;
+                let diameter = line.positive("sphere diameter")?;
+                let color = line.color("sphere color")?;
+                line.finish()?;
+
+                let material = Box::new(Lambertian::new(color));
+                world.add(Box::new(Sphere::new(center, diameter / 2.0, material)));
+            }
+            "pl" => {
+                let point = line.vector("plane point")?;
+                let normal = line.direction("plane normal")?;
+                let color = line.color("plane color")?;
+                line.finish()?;
+
+                let material = Box::new(Lambertian::new(color));
+                world.add(Box::new(Plane::new(point, normal, material)));
+            }
+            "cy" => {
+                let center = line.vector("cylinder center")?;
+                let axis = line.direction("cylinder axis")?;
+                let diameter = line.positive("cylinder diameter")?;
+                let height = line.positive("cylinder height")?;
+                let color = line.color("cylinder color")?;
+                line.finish()?;
+
+                let material = Box::new(Lambertian::new(color));
+                world.add(Box::new(Cylinder::new(center, axis, diameter / 2.0, height, material)));
+            }
+            other => return Err(line.error(id.column, format!("unknown element '{}'", other))),
+        }
+    }
+
+    if camera.is_none() {
+        return Err(RtError::Parse {
+            line: line_count + 1,
+            column: 1,
+            message: "missing camera ('C') element".to_string(),
+        });
+    }
+    if ambient.is_none() {
+        render.background(0.0, 0.0, 0.0);
+    }
+
+    Ok(Scene { world, instances: None, render })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAMERA: &str = "C 0,0,0 0,0,1 70\n";
+
+    // Parses `source`, returning the line, column and message of the error if any.
+    fn error(source: &str) -> Option<(usize, usize, String)> {
+        match parse(source, Render::new(8, 6, 1)) {
+            Ok(_) => None,
+            Err(RtError::Parse { line, column, message }) => Some((line, column, message)),
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_field() {
+        let (line, column, message) = error(&format!("{}\nsp 0,0,20 2 255,0,300\n", CAMERA)).unwrap();
+        assert_eq!((line, column), (3, 19));
+        assert_eq!(message, "sphere color channel 300 is out of range [0, 255]");
+
+        assert_eq!(
+            error(&format!("{}pl 0,0,0 0,1,x 255,255,255\n", CAMERA)),
+            Some((2, 14, "invalid plane normal 'x'".to_string())),
+        );
+        assert_eq!(
+            error(&format!("{}  sp 0,0 2 255,0,0\n", CAMERA)),
+            Some((2, 6, "sphere center '0,0' must have 3 components".to_string())),
+        );
+        assert_eq!(
+            error(&format!("{}sp 0,0,20 2\n", CAMERA)),
+            Some((2, 12, "missing sphere color".to_string())),
+        );
+        assert_eq!(
+            error(&format!("{}sp 0,0,20 2 255,0,0 extra\n", CAMERA)),
+            Some((2, 21, "unexpected 'extra'".to_string())),
+        );
+        assert_eq!(
+            error(&format!("{}# comment\ntr 0,0,0\n", CAMERA)),
+            Some((3, 1, "unknown element 'tr'".to_string())),
+        );
+    }
+
+    #[test]
+    fn values_are_range_checked() {
+        let message = |source: &str| error(&format!("{}{}\n", CAMERA, source)).map(|(_, _, message)| message);
+
+        assert_eq!(message("A 1.5 255,255,255").unwrap(), "ambient ratio 1.5 is out of range [0, 1]");
+        assert_eq!(message("L 0,0,0 -0.1 255,255,255").unwrap(), "light brightness -0.1 is out of range [0, 1]");
+        assert_eq!(message("sp 0,0,0 0 255,0,0").unwrap(), "sphere diameter must be positive, got 0");
+        assert_eq!(message("cy 0,0,0 0,1,0 1 -2 255,0,0").unwrap(), "cylinder height must be positive, got -2");
+        assert_eq!(message("pl 0,0,0 0,1.5,0 255,0,0").unwrap(), "plane normal component 1.5 is out of range [-1, 1]");
+        assert_eq!(message("pl 0,0,0 0,0,0 255,0,0").unwrap(), "plane normal must not be the zero vector");
+        assert_eq!(message("sp 0,0,0 1 -1,0,0").unwrap(), "sphere color channel -1 is out of range [0, 255]");
+        assert_eq!(message("sp 0,0,0 inf 255,0,0").unwrap(), "invalid sphere diameter 'inf'");
+        assert!(message("A 1 255,255,255\nL 0,0,0 0 0,0,0\nsp 0,0,0 1 0,0,0").is_none());
+
+        for fov in ["0", "180", "-10"] {
+            let source = format!("C 0,0,0 0,0,1 {}\n", fov);
+            assert_eq!(error(&source).unwrap().2, format!("field of view {} is out of range (0, 180)", fov));
+        }
+    }
+
+    #[test]
+    fn uppercase_elements_are_unique() {
+        for element in ["A 0.2 255,255,255", "L 0,10,0 0.5 255,255,255"] {
+            let source = format!("{}\n{}{}", element, CAMERA, element);
+            let (line, column, message) = error(&source).unwrap();
+            assert_eq!((line, column), (3, 1));
+            assert_eq!(message, format!("duplicate '{}' element (first declared on line 1)", &element[..1]));
+        }
+        assert_eq!(
+            error(&format!("{}{}", CAMERA, CAMERA)),
+            Some((2, 1, "duplicate 'C' element (first declared on line 1)".to_string())),
+        );
+    }
+
+    #[test]
+    fn camera_is_required() {
+        assert_eq!(
+            error("A 0.2 255,255,255\n\nsp 0,0,0 1 255,0,0\n"),
+            Some((4, 1, "missing camera ('C') element".to_string())),
+        );
+        assert_eq!(error(""), Some((1, 1, "missing camera ('C') element".to_string())));
+    }
+
+    #[test]
+    fn field_of_view_becomes_vertical() {
+        assert!((vertical_fov(90.0, 1.0) - 90.0).abs() < 1e-4);
+        // A 2:1 image keeps half the horizontal tangent vertically.
+        assert!((vertical_fov(90.0, 2.0) - 2.0 * 0.5f32.atan().to_degrees()).abs() < 1e-4);
+        assert!(vertical_fov(60.0, 4.0 / 3.0) < 60.0);
+        assert!(vertical_fov(60.0, 0.5) > 60.0);
+    }
+}
@@ -0,0 +1,24 @@
+use std::error::Error;
+use std::path::Path;
+use crate::bvh::Tlas;
+use crate::gltf;
+use crate::hits::HittableList;
+use crate::render::Render;
+use crate::rt;
+use crate::toml_scene;
+
+pub struct Scene {
+    pub world: HittableList,
+    // Instanced meshes, kept out of `world` so their top-level BVH can be reported.
+    pub instances: Option<Tlas>,
+    pub render: Render,
+}
+
+pub fn load(path: &Path, render: Render) -> Result<Scene, Box<dyn Error>> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("rt") => Ok(rt::load(path, render)?),
+        Some("toml") => Ok(toml_scene::load(path, render)?),
+        Some("gltf" | "glb") => Ok(gltf::load(path, render)?),
+        _ => Err(format!("unrecognised scene format (expected .rt, .toml, .gltf or .glb): {}", path.display()).into()),
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TileOrder {
+    Scanline,
+    Spiral,
+    Hilbert,
+}
+
+impl TileOrder {
+    pub fn from_name(name: &str) -> Option<TileOrder> {
+        match name.to_ascii_lowercase().as_str() {
+            "scanline" => Some(TileOrder::Scanline),
+            "spiral" => Some(TileOrder::Spiral),
+            "hilbert" => Some(TileOrder::Hilbert),
+            _ => None,
+        }
+    }
+}
+
+// A rectangle of pixels [x0, x1) x [y0, y1), with y growing down from the top row.
+#[derive(Copy, Clone, Debug)]
+pub struct Tile {
+    pub index: usize,
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize,
+}
+
+impl Tile {
+    pub fn pixel_count(&self) -> usize {
+        (self.x1 - self.x0) * (self.y1 - self.y0)
+    }
+}
+
+// Position of (x, y) along a Hilbert curve covering an n x n grid, n a power of two.
+fn hilbert_index(n: usize, mut x: usize, mut y: usize) -> usize {
+    let mut d = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as usize;
+        let ry = (y & s > 0) as usize;
+        d += s * s * ((3 * rx) ^ ry);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - (x & (s - 1));
+                y = s - 1 - (y & (s - 1));
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    d
+}
+
+// Walks a square spiral outwards from the central tile, keeping the cells inside the grid.
+fn spiral_order(columns: usize, rows: usize) -> Vec<(usize, usize)> {
+    let mut cells = Vec::with_capacity(columns * rows);
+    let (mut x, mut y) = ((columns as i64 - 1) / 2, (rows as i64 - 1) / 2);
+    let (mut dx, mut dy) = (1i64, 0i64);
+    let mut leg = 1;
+
+    while cells.len() < columns * rows {
+        for _ in 0..2 {
+            for _ in 0..leg {
+                if x >= 0 && y >= 0 && (x as usize) < columns && (y as usize) < rows {
+                    cells.push((x as usize, y as usize));
+                }
+                x += dx;
+                y += dy;
+            }
+            (dx, dy) = (-dy, dx);
+        }
+        leg += 1;
+    }
+    cells
+}
+
+pub fn tiles(width: usize, height: usize, tile_size: usize, order: TileOrder) -> Vec<Tile> {
+    let columns = width.div_ceil(tile_size);
+    let rows = height.div_ceil(tile_size);
+
+    let cells: Vec<(usize, usize)> = match order {
+        TileOrder::Scanline => (0..rows).flat_map(|y| (0..columns).map(move |x| (x, y))).collect(),
+        TileOrder::Spiral => spiral_order(columns, rows),
+        TileOrder::Hilbert => {
+            let n = columns.max(rows).next_power_of_two();
+            let mut cells: Vec<(usize, usize)> = (0..rows).flat_map(|y| (0..columns).map(move |x| (x, y))).collect();
+            cells.sort_by_key(|&(x, y)| hilbert_index(n, x, y));
+            cells
+        }
+    };
+
+    cells
+        .into_iter()
+        .map(|(column, row)| {
+            let x0 = column * tile_size;
+            let y0 = row * tile_size;
+            Tile {
+                // The index depends only on the tile's position so that seeded renders do
+                // not depend on the order or on which thread renders it.
+                index: row * columns + column,
+                x0,
+                y0,
+                x1: (x0 + tile_size).min(width),
+                y1: (y0 + tile_size).min(height),
+            }
+        })
+        .collect()
+}
+
+// One deque of tiles per worker. Workers take tiles from the front of their own deque and,
+// once it runs dry, steal from the back of the others'.
+pub struct WorkQueues {
+    queues: Vec<Mutex<VecDeque<Tile>>>,
+}
+
+impl WorkQueues {
+    pub fn new(tiles: Vec<Tile>, workers: usize) -> WorkQueues {
+        let mut queues: Vec<VecDeque<Tile>> = (0..workers).map(|_| VecDeque::new()).collect();
+        // Deal the tiles out in turn so every deque roughly follows the requested order.
+        for (i, tile) in tiles.into_iter().enumerate() {
+            queues[i % workers].push_back(tile);
+        }
+
+        WorkQueues {
+            queues: queues.into_iter().map(Mutex::new).collect(),
+        }
+    }
+
+    pub fn next(&self, worker: usize) -> Option<Tile> {
+        if let Some(tile) = self.queues[worker].lock().unwrap().pop_front() {
+            return Some(tile);
+        }
+
+        let workers = self.queues.len();
+        (1..workers)
+            .map(|offset| (worker + offset) % workers)
+            .find_map(|victim| self.queues[victim].lock().unwrap().pop_back())
+    }
+}
@@ -1,4 +1,5 @@
-use crate::hits::{HitRecord, Hittable};
+use crate::bounds::BBox;
+use crate::hits::{surface_spans, HitRecord, Hittable, Span};
 use crate::material::Material;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
@@ -7,17 +8,42 @@ pub struct Sphere {
     pub center: Vec3,
     pub radius: f32,
     pub material: Box<dyn Material>,
+    motion: Option<Motion>,
+}
+
+// A straight move from the sphere's `center` at `time0` to `to` at `time1`; the sphere
+// rests at either end outside that interval.
+struct Motion {
+    to: Vec3,
+    time0: f32,
+    time1: f32,
 }
 
 impl Sphere {
     pub fn new(center: Vec3, radius: f32, material: Box<dyn Material>) -> Sphere {
-        Sphere { center, radius, material }
+        Sphere { center, radius, material, motion: None }
+    }
+
+    pub fn moving(center0: Vec3, center1: Vec3, time0: f32, time1: f32, radius: f32, material: Box<dyn Material>) -> Sphere {
+        Sphere { center: center0, radius, material, motion: Some(Motion { to: center1, time0, time1 }) }
+    }
+
+    fn center_at(&self, time: f32) -> Vec3 {
+        match &self.motion {
+            Some(motion) if motion.time1 > motion.time0 => {
+                let f = ((time - motion.time0) / (motion.time1 - motion.time0)).clamp(0.0, 1.0);
+                self.center + (motion.to - self.center) * f
+            }
+            Some(motion) if time >= motion.time1 => motion.to,
+            _ => self.center,
+        }
     }
 }
 
 impl Hittable for Sphere {
     fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
-        let oc = ray.origin() - self.center;
+        let center = self.center_at(ray.time());
+        let oc = ray.origin() - center;
         let a = ray.direction().dot(ray.direction());
         let b = oc.dot(ray.direction());
         let c = oc.dot(oc) - self.radius * self.radius;
@@ -29,11 +55,15 @@ impl Hittable for Sphere {
             let temp = (-b - sqrt_discriminant) / a;
             if temp < t_max && temp > t_min {
                 let p = ray.at(temp);
-                let normal = (p - self.center) / self.radius;
+                let normal = (p - center) / self.radius;
                 return Some(HitRecord {
                     t: temp,
                     p,
                     normal,
+                    u: 0.0,
+                    v: 0.0,
+                    uv: None,
+                    color: None,
                     material: self.material.as_ref(),
                 });
             }
@@ -41,15 +71,34 @@ impl Hittable for Sphere {
             let temp = (-b + sqrt_discriminant) / a;
             if temp < t_max && temp > t_min {
                 let p = ray.at(temp);
-                let normal = (p - self.center) / self.radius;
+                let normal = (p - center) / self.radius;
                 return Some(HitRecord {
                     t: temp,
                     p,
                     normal,
+                    u: 0.0,
+                    v: 0.0,
+                    uv: None,
+                    color: None,
                     material: self.material.as_ref(),
                 });
             }
         }
         None
     }
+
+    fn bounding_box(&self) -> BBox {
+        let r = self.radius.abs();
+        let rvec = Vec3::new(r, r, r);
+        let start = BBox::from_points(self.center - rvec, self.center + rvec);
+        // A moving sphere is bounded over its whole path, so the box holds at any time.
+        match &self.motion {
+            Some(motion) => BBox::surrounding(&start, &BBox::from_points(motion.to - rvec, motion.to + rvec)),
+            None => start,
+        }
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        surface_spans(self, ray)
+    }
 }
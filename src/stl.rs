@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::material::Material;
+use crate::mesh::{Face, Mesh};
+use crate::vec3::Vec3;
+
+const HEADER_SIZE: usize = 84;
+const TRIANGLE_SIZE: usize = 50;
+
+#[derive(Debug)]
+pub enum StlError {
+    Io(PathBuf, io::Error),
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for StlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StlError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            StlError::Invalid(path, message) => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl Error for StlError {}
+
+fn read_vec3(bytes: &[u8]) -> [u32; 3] {
+    let word = |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+    [word(0), word(4), word(8)]
+}
+
+// Loads a binary STL file. STL stores every triangle with its own copy of the corners,
+// so identical corners are merged back into shared vertices.
+pub fn load(path: &Path, material: Box<dyn Material>) -> Result<Mesh, StlError> {
+    let bytes = fs::read(path).map_err(|e| StlError::Io(path.to_path_buf(), e))?;
+    parse(path, &bytes, material)
+}
+
+fn parse(path: &Path, bytes: &[u8], material: Box<dyn Material>) -> Result<Mesh, StlError> {
+    let invalid = |message: &str| StlError::Invalid(path.to_path_buf(), message.to_string());
+
+    if bytes.len() < HEADER_SIZE {
+        return Err(invalid("file is too short for a binary STL header"));
+    }
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    let expected = count.checked_mul(TRIANGLE_SIZE).and_then(|size| size.checked_add(HEADER_SIZE));
+    if expected != Some(bytes.len()) {
+        if bytes.starts_with(b"solid") {
+            return Err(invalid("ASCII STL is not supported, only binary"));
+        }
+        return Err(invalid("file size does not match its triangle count"));
+    }
+
+    let mut mesh = Mesh::new();
+    mesh.add_material(material);
+    let mut vertices: HashMap<[u32; 3], usize> = HashMap::new();
+
+    for triangle in bytes[HEADER_SIZE..].chunks_exact(TRIANGLE_SIZE) {
+        // The stored facet normal is skipped; the winding gives the same orientation.
+        let mut positions = [0; 3];
+        for (corner, position) in positions.iter_mut().enumerate() {
+            let bits = read_vec3(&triangle[12 + corner * 12..]);
+            if !bits.iter().all(|&b| f32::from_bits(b).is_finite()) {
+                return Err(invalid("vertex coordinates must be finite"));
+            }
+            *position = *vertices.entry(bits).or_insert_with(|| {
+                let [x, y, z] = bits.map(f32::from_bits);
+                mesh.positions.push(Vec3::new(x, y, z));
+                mesh.positions.len() - 1
+            });
+        }
+        mesh.add_face(Face { positions, normals: None, uvs: None, material: 0 });
+    }
+
+    if mesh.faces.is_empty() {
+        return Err(invalid("mesh has no faces"));
+    }
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    // A binary STL declaring `count` triangles and holding `triangles`.
+    fn stl(count: u32, triangles: &[[[f32; 3]; 3]]) -> Vec<u8> {
+        let mut bytes = vec![0u8; 80];
+        bytes.extend(count.to_le_bytes());
+        for triangle in triangles {
+            bytes.extend([0u8; 12]);
+            for value in triangle.as_flattened() {
+                bytes.extend(value.to_le_bytes());
+            }
+            bytes.extend([0u8; 2]);
+        }
+        bytes
+    }
+
+    fn mesh(bytes: &[u8]) -> Result<Mesh, String> {
+        parse(Path::new("test.stl"), bytes, Box::new(Lambertian::new(Vec3::new(1.0, 1.0, 1.0))))
+            .map_err(|e| e.to_string())
+    }
+
+    const SQUARE: [[[f32; 3]; 3]; 2] = [
+        [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+        [[0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+    ];
+
+    #[test]
+    fn shared_corners_are_merged() {
+        let mesh = mesh(&stl(2, &SQUARE)).unwrap();
+        assert_eq!(mesh.positions.len(), 4);
+        let faces: Vec<[usize; 3]> = mesh.faces.iter().map(|face| face.positions).collect();
+        assert_eq!(faces, [[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn file_size_must_match_the_triangle_count() {
+        let mismatch = "test.stl: file size does not match its triangle count";
+        assert_eq!(mesh(&stl(3, &SQUARE)).err().unwrap(), mismatch);
+        assert_eq!(mesh(&stl(1, &SQUARE)).err().unwrap(), mismatch);
+        let bytes = stl(2, &SQUARE);
+        assert_eq!(mesh(&bytes[..bytes.len() - 1]).err().unwrap(), mismatch);
+        // Big enough to overflow the expected size on 32-bit targets.
+        assert_eq!(mesh(&stl(u32::MAX, &SQUARE)).err().unwrap(), mismatch);
+
+        assert_eq!(mesh(&bytes[..83]).err().unwrap(), "test.stl: file is too short for a binary STL header");
+        let mut ascii = b"solid square\n".to_vec();
+        ascii.resize(100, b' ');
+        assert_eq!(mesh(&ascii).err().unwrap(), "test.stl: ASCII STL is not supported, only binary");
+    }
+
+    #[test]
+    fn empty_and_invalid_meshes_are_errors() {
+        assert_eq!(mesh(&stl(0, &[])).err().unwrap(), "test.stl: mesh has no faces");
+        let flat = [[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [2.0, 0.0, 0.0]]];
+        assert_eq!(mesh(&stl(1, &flat)).err().unwrap(), "test.stl: mesh has no faces");
+        let infinite = [[[0.0, 0.0, 0.0], [f32::INFINITY, 0.0, 0.0], [0.0, 1.0, 0.0]]];
+        assert_eq!(mesh(&stl(1, &infinite)).err().unwrap(), "test.stl: vertex coordinates must be finite");
+    }
+}
@@ -0,0 +1,666 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use serde::Deserialize;
+use crate::cone::Cone;
+use crate::csg::{Csg, Operation};
+use crate::cuboid::Cuboid;
+use crate::cylinder::Cylinder;
+use crate::disk::Disk;
+use crate::gltf;
+use crate::hits::{Hittable, HittableList};
+use crate::bvh::Tlas;
+use crate::instance::Instance;
+use crate::mesh::Mesh;
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::medium::ConstantMedium;
+use crate::obj;
+use crate::ply;
+use crate::plane::Plane;
+use crate::quad::Quad;
+use crate::render::Render;
+use crate::scene::Scene;
+use crate::sphere::Sphere;
+use crate::stl;
+use crate::torus::Torus;
+use crate::transform::Transform;
+use crate::triangle::Triangle;
+use crate::vec3::Vec3;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Invalid(String),
+    Mesh(Box<dyn Error>),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "{}", e),
+            SceneError::Toml(e) => write!(f, "{}", e),
+            SceneError::Invalid(message) => write!(f, "{}", message),
+            SceneError::Mesh(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for SceneError {}
+
+impl From<io::Error> for SceneError {
+    fn from(e: io::Error) -> Self {
+        SceneError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for SceneError {
+    fn from(e: toml::de::Error) -> Self {
+        SceneError::Toml(e)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDef {
+    #[serde(default)]
+    camera: CameraDef,
+    background: Option<[f32; 3]>,
+    #[serde(default)]
+    materials: BTreeMap<String, MaterialDef>,
+    #[serde(default)]
+    objects: Vec<ObjectDef>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct CameraDef {
+    lookfrom: Option<[f32; 3]>,
+    lookat: Option<[f32; 3]>,
+    vup: Option<[f32; 3]>,
+    vfov: Option<f32>,
+    aperture: Option<f32>,
+    focus_dist: Option<f32>,
+    ipd: Option<f32>,
+    // Open and close times; rays are spread over them to blur moving objects.
+    shutter: Option<[f32; 2]>,
+}
+
+fn white() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn yes() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum MaterialDef {
+    Lambertian {
+        albedo: [f32; 3],
+    },
+    Metal {
+        albedo: [f32; 3],
+        #[serde(default)]
+        fuzz: f32,
+    },
+    Dielectric {
+        ref_idx: f32,
+        #[serde(default = "white")]
+        tint: [f32; 3],
+    },
+    Light {
+        emit: [f32; 3],
+    },
+}
+
+impl MaterialDef {
+    fn validate(&self, name: &str) -> Result<(), SceneError> {
+        match self {
+            MaterialDef::Metal { fuzz, .. } if *fuzz < 0.0 => {
+                Err(SceneError::Invalid(format!("material '{}': fuzz must not be negative", name)))
+            }
+            MaterialDef::Dielectric { ref_idx, .. } if *ref_idx <= 0.0 => {
+                Err(SceneError::Invalid(format!("material '{}': ref_idx must be positive", name)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn build(&self) -> Arc<dyn Material> {
+        match self {
+            MaterialDef::Lambertian { albedo } => Arc::new(Lambertian::new(vec3(*albedo))),
+            MaterialDef::Metal { albedo, fuzz } => Arc::new(Metal::new(vec3(*albedo), *fuzz)),
+            MaterialDef::Dielectric { ref_idx, tint } => Arc::new(Dielectric::new(*ref_idx, vec3(*tint))),
+            MaterialDef::Light { emit } => Arc::new(DiffuseLight::new(vec3(*emit))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum ObjectDef {
+    // With `center1`, the sphere moves from `center` at `time0` (default 0) to `center1`
+    // at `time1` (default 1), staying put before and after.
+    Sphere {
+        center: [f32; 3],
+        center1: Option<[f32; 3]>,
+        time0: Option<f32>,
+        time1: Option<f32>,
+        radius: f32,
+        material: String,
+    },
+    Plane {
+        point: [f32; 3],
+        normal: [f32; 3],
+        material: String,
+    },
+    Cylinder {
+        center: [f32; 3],
+        axis: [f32; 3],
+        radius: f32,
+        height: f32,
+        material: String,
+    },
+    // Either `radius` (of the base) or `angle` (half-angle at the apex, in degrees) sets the width.
+    Cone {
+        apex: [f32; 3],
+        axis: [f32; 3],
+        height: f32,
+        radius: Option<f32>,
+        angle: Option<f32>,
+        #[serde(default = "yes")]
+        closed: bool,
+        material: String,
+    },
+    // A ring around `axis`: a tube of `minor_radius` swept along a circle of `major_radius`.
+    Torus {
+        center: [f32; 3],
+        axis: [f32; 3],
+        major_radius: f32,
+        minor_radius: f32,
+        material: String,
+    },
+    // The parallelogram spanned by the edges `u` and `v` from the corner `q`.
+    Quad {
+        q: [f32; 3],
+        u: [f32; 3],
+        v: [f32; 3],
+        material: String,
+    },
+    // A flat disk facing along `normal`; a ring when `inner_radius` is set.
+    Disk {
+        center: [f32; 3],
+        normal: [f32; 3],
+        radius: f32,
+        #[serde(default)]
+        inner_radius: f32,
+        material: String,
+    },
+    // An axis-aligned box between two opposite corners.
+    Box {
+        min: [f32; 3],
+        max: [f32; 3],
+        material: String,
+    },
+    // Vertices in counter-clockwise order as seen from the front.
+    Triangle {
+        vertices: [[f32; 3]; 3],
+        material: String,
+    },
+    // The union, intersection or difference (left minus right) of two closed shapes, given
+    // as nested object tables; operands can be csg objects themselves.
+    Csg {
+        operation: OperationDef,
+        left: Box<ObjectDef>,
+        right: Box<ObjectDef>,
+    },
+    // Smoke or fog of constant `density` filling a closed shape, given as a nested object
+    // table that needs no material; `albedo` is the color the particles scatter.
+    Medium {
+        #[serde(deserialize_with = "boundary")]
+        boundary: Box<ObjectDef>,
+        density: f32,
+        albedo: [f32; 3],
+    },
+    // A mesh file, relative to the scene file. `material` applies to faces the file
+    // doesn't assign a material to. Objects using the same file and material share one
+    // copy of the geometry, each placed by its own scale, rotation (degrees around x,
+    // then y, then z) and translation.
+    Mesh {
+        path: PathBuf,
+        material: Option<String>,
+        translate: Option<[f32; 3]>,
+        rotate: Option<[f32; 3]>,
+        scale: Option<ScaleDef>,
+    },
+}
+
+// Reads a medium's boundary, whose material (and those of csg operands inside it) may be
+// left out; an omitted material reads as the empty name.
+fn boundary<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Box<ObjectDef>, D::Error> {
+    fn fill(table: &mut toml::Table) {
+        if table.get("type").and_then(toml::Value::as_str) == Some("csg") {
+            for operand in ["left", "right"] {
+                if let Some(toml::Value::Table(operand)) = table.get_mut(operand) {
+                    fill(operand);
+                }
+            }
+        } else {
+            table.entry("material").or_insert_with(|| toml::Value::String(String::new()));
+        }
+    }
+    let mut table = toml::Table::deserialize(deserializer)?;
+    fill(&mut table);
+    toml::Value::Table(table).try_into().map_err(serde::de::Error::custom)
+}
+
+impl ObjectDef {
+    // Only shapes with an inside can take part in CSG.
+    fn is_closed(&self) -> bool {
+        match self {
+            ObjectDef::Sphere { .. } | ObjectDef::Cylinder { .. } | ObjectDef::Torus { .. } => true,
+            ObjectDef::Box { .. } | ObjectDef::Csg { .. } => true,
+            ObjectDef::Cone { closed, .. } => *closed,
+            _ => false,
+        }
+    }
+
+    // When a moving object, or one of its parts, is in motion.
+    fn motion(&self) -> Option<(f32, f32)> {
+        match self {
+            ObjectDef::Sphere { center1: Some(_), time0, time1, .. } => Some((time0.unwrap_or(0.0), time1.unwrap_or(1.0))),
+            ObjectDef::Csg { left, right, .. } => match (left.motion(), right.motion()) {
+                (Some(a), Some(b)) => Some((a.0.min(b.0), a.1.max(b.1))),
+                (a, b) => a.or(b),
+            },
+            ObjectDef::Medium { boundary, .. } => boundary.motion(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum OperationDef {
+    Union,
+    Intersection,
+    Difference,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScaleDef {
+    Uniform(f32),
+    Axes([f32; 3]),
+}
+
+fn vec3(v: [f32; 3]) -> Vec3 {
+    Vec3::new(v[0], v[1], v[2])
+}
+
+// Each named material is built once and shared by every object that uses it.
+struct Materials {
+    built: BTreeMap<String, Arc<dyn Material>>,
+    // Stands in for an omitted material on objects that are never shaded.
+    fallback: Option<Arc<dyn Material>>,
+}
+
+impl Materials {
+    // Materials are checked in name order, so the first error reported doesn't vary.
+    fn new(defs: &BTreeMap<String, MaterialDef>) -> Result<Materials, SceneError> {
+        let mut built = BTreeMap::new();
+        for (name, def) in defs {
+            def.validate(name)?;
+            built.insert(name.clone(), def.build());
+        }
+        Ok(Materials { built, fallback: None })
+    }
+
+    // For shapes that only bound something else, so their materials can be left out.
+    fn for_boundaries(&self) -> Materials {
+        let fallback: Arc<dyn Material> = Arc::new(Lambertian::new(Vec3::new(0.8, 0.8, 0.8)));
+        Materials { built: self.built.clone(), fallback: Some(fallback) }
+    }
+
+    fn get(&self, name: &str, object: usize) -> Result<Box<dyn Material>, SceneError> {
+        match (self.built.get(name), &self.fallback) {
+            (Some(material), _) => Ok(Box::new(material.clone())),
+            (None, Some(material)) if name.is_empty() => Ok(Box::new(material.clone())),
+            (None, _) => Err(SceneError::Invalid(format!("objects[{}]: unknown material '{}'", object, name))),
+        }
+    }
+
+    fn get_or_default(&self, name: &Option<String>, object: usize) -> Result<Box<dyn Material>, SceneError> {
+        match name {
+            Some(name) => self.get(name, object),
+            None => Ok(Box::new(Lambertian::new(Vec3::new(0.8, 0.8, 0.8)))),
+        }
+    }
+}
+
+fn load_mesh(path: &Path, material: Box<dyn Material>) -> Result<Mesh, SceneError> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("obj") => obj::load(path, material).map_err(|e| SceneError::Mesh(Box::new(e))),
+        Some("ply") => ply::load(path, material).map_err(|e| SceneError::Mesh(Box::new(e))),
+        Some("stl") => stl::load(path, material).map_err(|e| SceneError::Mesh(Box::new(e))),
+        Some("gltf" | "glb") => gltf::load_mesh(path, material).map_err(|e| SceneError::Mesh(Box::new(e))),
+        _ => Err(SceneError::Invalid(format!(
+            "unrecognised mesh format (expected .obj, .ply, .stl, .gltf or .glb): {}",
+            path.display()
+        ))),
+    }
+}
+
+// Builds any object other than a mesh; also used for the operands of CSG objects.
+fn shape(object: &ObjectDef, index: usize, materials: &Materials) -> Result<Box<dyn Hittable>, SceneError> {
+    match object {
+        ObjectDef::Sphere { center, center1, time0, time1, radius, material } => {
+            if *radius <= 0.0 {
+                return Err(SceneError::Invalid(format!("objects[{}]: sphere radius must be positive", index)));
+            }
+            let material = materials.get(material, index)?;
+            match center1 {
+                Some(center1) => {
+                    let (time0, time1) = (time0.unwrap_or(0.0), time1.unwrap_or(1.0));
+                    if time1 <= time0 {
+                        return Err(SceneError::Invalid(format!("objects[{}]: sphere time1 must be after time0", index)));
+                    }
+                    Ok(Box::new(Sphere::moving(vec3(*center), vec3(*center1), time0, time1, *radius, material)))
+                }
+                None if time0.is_some() || time1.is_some() => {
+                    Err(SceneError::Invalid(format!("objects[{}]: sphere time0 and time1 need a center1", index)))
+                }
+                None => Ok(Box::new(Sphere::new(vec3(*center), *radius, material))),
+            }
+        }
+        ObjectDef::Plane { point, normal, material } => {
+            if vec3(*normal).near_zero() {
+                return Err(SceneError::Invalid(format!("objects[{}]: plane normal must not be zero", index)));
+            }
+            let material = materials.get(material, index)?;
+            Ok(Box::new(Plane::new(vec3(*point), vec3(*normal), material)))
+        }
+        ObjectDef::Cylinder { center, axis, radius, height, material } => {
+            if vec3(*axis).near_zero() {
+                return Err(SceneError::Invalid(format!("objects[{}]: cylinder axis must not be zero", index)));
+            }
+            if *radius <= 0.0 || *height <= 0.0 {
+                return Err(SceneError::Invalid(format!("objects[{}]: cylinder radius and height must be positive", index)));
+            }
+            let material = materials.get(material, index)?;
+            Ok(Box::new(Cylinder::new(vec3(*center), vec3(*axis), *radius, *height, material)))
+        }
+        ObjectDef::Cone { apex, axis, height, radius, angle, closed, material } => {
+            if vec3(*axis).near_zero() {
+                return Err(SceneError::Invalid(format!("objects[{}]: cone axis must not be zero", index)));
+            }
+            if *height <= 0.0 {
+                return Err(SceneError::Invalid(format!("objects[{}]: cone height must be positive", index)));
+            }
+            let (apex, axis) = (vec3(*apex), vec3(*axis));
+            let cone = match (radius, angle) {
+                (Some(radius), None) if *radius > 0.0 => {
+                    Cone::new(apex, axis, *radius, *height, *closed, materials.get(material, index)?)
+                }
+                (None, Some(angle)) if *angle > 0.0 && *angle < 90.0 => {
+                    Cone::with_angle(apex, axis, *angle, *height, *closed, materials.get(material, index)?)
+                }
+                (Some(_), None) => {
+                    return Err(SceneError::Invalid(format!("objects[{}]: cone radius must be positive", index)));
+                }
+                (None, Some(_)) => {
+                    return Err(SceneError::Invalid(format!("objects[{}]: cone angle must be in (0, 90)", index)));
+                }
+                _ => {
+                    return Err(SceneError::Invalid(format!("objects[{}]: cone needs exactly one of radius or angle", index)));
+                }
+            };
+            Ok(Box::new(cone))
+        }
+        ObjectDef::Torus { center, axis, major_radius, minor_radius, material } => {
+            if vec3(*axis).near_zero() {
+                return Err(SceneError::Invalid(format!("objects[{}]: torus axis must not be zero", index)));
+            }
+            if *major_radius <= 0.0 || *minor_radius <= 0.0 {
+                return Err(SceneError::Invalid(format!("objects[{}]: torus radii must be positive", index)));
+            }
+            // A tube wider than the ring passes through the axis and intersects itself.
+            if *minor_radius >= *major_radius {
+                return Err(SceneError::Invalid(format!(
+                    "objects[{}]: torus minor_radius must be smaller than major_radius",
+                    index
+                )));
+            }
+            let material = materials.get(material, index)?;
+            Ok(Box::new(Torus::new(vec3(*center), vec3(*axis), *major_radius, *minor_radius, material)))
+        }
+        ObjectDef::Quad { q, u, v, material } => {
+            if vec3(*u).cross(vec3(*v)).near_zero() {
+                return Err(SceneError::Invalid(format!("objects[{}]: quad edges must not be parallel", index)));
+            }
+            let material = materials.get(material, index)?;
+            Ok(Box::new(Quad::new(vec3(*q), vec3(*u), vec3(*v), material)))
+        }
+        ObjectDef::Disk { center, normal, radius, inner_radius, material } => {
+            if vec3(*normal).near_zero() {
+                return Err(SceneError::Invalid(format!("objects[{}]: disk normal must not be zero", index)));
+            }
+            if *radius <= 0.0 || *inner_radius < 0.0 || *inner_radius >= *radius {
+                return Err(SceneError::Invalid(format!(
+                    "objects[{}]: disk radius must be positive and inner_radius in [0, radius)",
+                    index
+                )));
+            }
+            let material = materials.get(material, index)?;
+            Ok(Box::new(Disk::new(vec3(*center), vec3(*normal), *radius, *inner_radius, material)))
+        }
+        ObjectDef::Box { min, max, material } => {
+            // A flat box has faces with no area, so it has no inside to enclose.
+            if (0..3).any(|i| min[i] == max[i]) {
+                return Err(SceneError::Invalid(format!("objects[{}]: box must have non-zero size on every axis", index)));
+            }
+            let material = materials.get(material, index)?;
+            Ok(Box::new(Cuboid::new(vec3(*min), vec3(*max), material)))
+        }
+        ObjectDef::Triangle { vertices: [a, b, c], material } => {
+            let (a, b, c) = (vec3(*a), vec3(*b), vec3(*c));
+            if (b - a).cross(c - a).near_zero() {
+                return Err(SceneError::Invalid(format!("objects[{}]: triangle is degenerate", index)));
+            }
+            let material = materials.get(material, index)?;
+            Ok(Box::new(Triangle::new(a, b, c, material)))
+        }
+        ObjectDef::Csg { operation, left, right } => {
+            if !left.is_closed() || !right.is_closed() {
+                return Err(SceneError::Invalid(format!(
+                    "objects[{}]: csg operands must be closed shapes (sphere, cylinder, closed cone, torus, box or csg)",
+                    index
+                )));
+            }
+            let operation = match operation {
+                OperationDef::Union => Operation::Union,
+                OperationDef::Intersection => Operation::Intersection,
+                OperationDef::Difference => Operation::Difference,
+            };
+            Ok(Box::new(Csg::new(operation, shape(left, index, materials)?, shape(right, index, materials)?)))
+        }
+        ObjectDef::Medium { boundary, density, albedo } => {
+            if !boundary.is_closed() {
+                return Err(SceneError::Invalid(format!(
+                    "objects[{}]: medium boundary must be a closed shape (sphere, cylinder, closed cone, torus, box or csg)",
+                    index
+                )));
+            }
+            if *density <= 0.0 {
+                return Err(SceneError::Invalid(format!("objects[{}]: medium density must be positive", index)));
+            }
+            // The boundary is never shaded, so its material is optional.
+            let boundary = shape(boundary, index, &materials.for_boundaries())?;
+            Ok(Box::new(ConstantMedium::new(boundary, *density, vec3(*albedo))))
+        }
+        ObjectDef::Mesh { .. } => unreachable!("meshes are instanced by the caller"),
+    }
+}
+
+pub fn load(path: &Path, render: Render) -> Result<Scene, SceneError> {
+    let source = fs::read_to_string(path)?;
+    parse(&source, path.parent().unwrap_or(Path::new("")), render)
+}
+
+// Relative mesh paths are resolved against `base_dir`.
+pub fn parse(source: &str, base_dir: &Path, mut render: Render) -> Result<Scene, SceneError> {
+    let def: SceneDef = toml::from_str(source)?;
+
+    let materials = Materials::new(&def.materials)?;
+
+    let camera = def.camera;
+    if let Some([x, y, z]) = camera.lookfrom { render.lookfrom(x, y, z); }
+    if let Some([x, y, z]) = camera.lookat { render.lookat(x, y, z); }
+    if let Some([x, y, z]) = camera.vup { render.vup(x, y, z); }
+    if let Some(vfov) = camera.vfov { render.vfov(vfov); }
+    if let Some(aperture) = camera.aperture { render.aperture(aperture); }
+    if let Some(focus_dist) = camera.focus_dist { render.focus_dist(focus_dist); }
+    if let Some(ipd) = camera.ipd { render.ipd(ipd); }
+    // Without a shutter, it stays open while anything in the scene moves, so motion blurs.
+    let shutter = match camera.shutter {
+        Some([open, close]) => Some((open, close)),
+        None => def.objects.iter().filter_map(ObjectDef::motion).reduce(|a, b| (a.0.min(b.0), a.1.max(b.1))),
+    };
+    if let Some((open, close)) = shutter {
+        if close < open {
+            return Err(SceneError::Invalid("camera: shutter must close after it opens".to_string()));
+        }
+        render.shutter(open, close);
+    }
+    if let Some([r, g, b]) = def.background { render.background(r, g, b); }
+
+    let mut world = HittableList::new();
+    let mut meshes: HashMap<(PathBuf, Option<String>), Arc<dyn Hittable>> = HashMap::new();
+    let mut instances = Vec::new();
+    for (index, object) in def.objects.iter().enumerate() {
+        match object {
+            ObjectDef::Mesh { path, material, translate, rotate, scale } => {
+                let scale = match scale {
+                    Some(ScaleDef::Uniform(s)) => Vec3::new(*s, *s, *s),
+                    Some(ScaleDef::Axes(s)) => vec3(*s),
+                    None => Vec3::new(1.0, 1.0, 1.0),
+                };
+                let translate = translate.map_or(Vec3::new(0.0, 0.0, 0.0), vec3);
+                let rotate = rotate.map_or(Vec3::new(0.0, 0.0, 0.0), vec3);
+                let transform = match Transform::from_trs(translate, rotate, scale) {
+                    Some(transform) => transform,
+                    None => return Err(SceneError::Invalid(format!("objects[{}]: scale must not be zero", index))),
+                };
+
+                let key = (base_dir.join(path), material.clone());
+                let mesh = match meshes.get(&key) {
+                    Some(mesh) => mesh.clone(),
+                    None => {
+                        let material = materials.get_or_default(material, index)?;
+                        let mesh: Arc<dyn Hittable> = Arc::new(load_mesh(&key.0, material)?.into_bvh());
+                        meshes.insert(key, mesh.clone());
+                        mesh
+                    }
+                };
+                instances.push(Instance::new(mesh, transform));
+            }
+            _ => world.add(shape(object, index, &materials)?),
+        }
+    }
+
+    // Mesh instances share a top-level BVH over the per-mesh BVHs.
+    let instances = if instances.is_empty() { None } else { Some(Tlas::new(instances)) };
+
+    Ok(Scene { world, instances, render })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Parses `objects` after a definition of the material "white", returning the error if any.
+    fn error(objects: &str) -> Option<String> {
+        let source = format!("[materials.white]\ntype = \"lambertian\"\nalbedo = [1, 1, 1]\n{}", objects);
+        parse(&source, Path::new(""), Render::new(8, 6, 1)).err().map(|e| e.to_string())
+    }
+
+    #[test]
+    fn boxes_need_thickness() {
+        let flat = "[[objects]]\ntype = \"box\"\nmin = [0, 0, 0]\nmax = [1, 0, 1]\nmaterial = \"white\"";
+        assert!(error(flat).unwrap().contains("non-zero size"));
+        assert_eq!(error(&flat.replace("[1, 0, 1]", "[1, -1, 1]")), None);
+    }
+
+    #[test]
+    fn torus_tubes_must_fit_inside_the_ring() {
+        let torus = "[[objects]]\ntype = \"torus\"\ncenter = [0, 0, 0]\naxis = [0, 1, 0]\nmajor_radius = 1\nminor_radius = 1\nmaterial = \"white\"";
+        assert!(error(torus).unwrap().contains("minor_radius"));
+        assert_eq!(error(&torus.replace("minor_radius = 1", "minor_radius = 0.5")), None);
+    }
+
+    #[test]
+    fn objects_report_when_they_move() {
+        let source = r#"
+            [[objects]]
+            type = "sphere"
+            center = [0, 0, 0]
+            radius = 1
+            material = "white"
+
+            [[objects]]
+            type = "sphere"
+            center = [0, 0, 0]
+            center1 = [1, 0, 0]
+            time0 = 0.25
+            radius = 1
+            material = "white"
+
+            [[objects]]
+            type = "csg"
+            operation = "union"
+            left = { type = "sphere", center = [0, 0, 0], radius = 1, material = "white" }
+            right = { type = "sphere", center = [0, 0, 0], center1 = [1, 0, 0], time1 = 2, radius = 1, material = "white" }
+        "#;
+        let def: SceneDef = toml::from_str(source).unwrap();
+        let motion: Vec<_> = def.objects.iter().map(ObjectDef::motion).collect();
+        assert_eq!(motion, vec![None, Some((0.25, 1.0)), Some((0.0, 2.0))]);
+    }
+
+    #[test]
+    fn spheres_need_a_positive_radius() {
+        let sphere = "[[objects]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\nmaterial = \"white\"\n";
+        for radius in ["0", "-1"] {
+            assert_eq!(
+                error(&format!("{}radius = {}", sphere, radius)).unwrap(),
+                "objects[0]: sphere radius must be positive",
+            );
+        }
+        assert_eq!(error(&format!("{}radius = 0.5", sphere)), None);
+    }
+
+    #[test]
+    fn sphere_motion_must_move_forward_in_time() {
+        let sphere = "[[objects]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\nradius = 1\nmaterial = \"white\"\n";
+        assert!(error(&format!("{}center1 = [1, 0, 0]\ntime0 = 1\ntime1 = 1", sphere)).unwrap().contains("time1"));
+        assert!(error(&format!("{}time1 = 2", sphere)).unwrap().contains("center1"));
+        assert_eq!(error(&format!("{}center1 = [1, 0, 0]\ntime0 = 1\ntime1 = 2", sphere)), None);
+    }
+
+    #[test]
+    fn medium_boundaries_need_no_material() {
+        let medium = "[[objects]]\ntype = \"medium\"\ndensity = 1\nalbedo = [1, 1, 1]\nboundary = { type = \"sphere\", center = [0, 0, 0], radius = 1 }";
+        assert_eq!(error(medium), None);
+        assert_eq!(error(&medium.replace("radius = 1", "radius = 1, material = \"white\"")), None);
+        let typo = error(&medium.replace("radius = 1", "radius = 1, material = \"undefined\""));
+        assert!(typo.unwrap().contains("unknown material 'undefined'"));
+
+        let sphere = "[[objects]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\nradius = 1";
+        assert!(error(sphere).unwrap().contains("missing field `material`"));
+    }
+}
@@ -0,0 +1,256 @@
+use crate::bounds::BBox;
+use crate::hits::{surface_spans, HitRecord, Hittable, Span};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// A ring around `axis` through `center`: the tube of `minor_radius` swept along a
+// circle of `major_radius`.
+pub struct Torus {
+    pub center: Vec3,
+    pub axis: Vec3,
+    pub major_radius: f32,
+    pub minor_radius: f32,
+    pub material: Box<dyn Material>,
+    // Completes `axis` to an orthonormal frame in which the torus lies in the xz plane.
+    u: Vec3,
+    w: Vec3,
+}
+
+impl Torus {
+    pub fn new(center: Vec3, axis: Vec3, major_radius: f32, minor_radius: f32, material: Box<dyn Material>) -> Torus {
+        let axis = axis.unit_vector();
+        let helper = if axis.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let u = axis.cross(helper).unit_vector();
+        let w = u.cross(axis);
+        Torus { center, axis, major_radius, minor_radius, material, u, w }
+    }
+
+    fn to_local(&self, v: Vec3) -> [f64; 3] {
+        [v.dot(self.u) as f64, v.dot(self.axis) as f64, v.dot(self.w) as f64]
+    }
+}
+
+fn evaluate(poly: &[f64], x: f64) -> f64 {
+    poly.iter().rev().fold(0.0, |acc, c| acc * x + c)
+}
+
+// Finds the root of `poly` in [lo, hi], given that its value changes sign there, by
+// Newton's method safeguarded with bisection.
+fn refine_root(poly: &[f64], derivative: &[f64], mut lo: f64, mut hi: f64) -> f64 {
+    let rising = evaluate(poly, hi) > evaluate(poly, lo);
+    let mut x = 0.5 * (lo + hi);
+    for _ in 0..64 {
+        let value = evaluate(poly, x);
+        if value == 0.0 {
+            return x;
+        }
+        if (value > 0.0) == rising { hi = x } else { lo = x }
+
+        let slope = evaluate(derivative, x);
+        let newton = x - value / slope;
+        let next = if newton > lo && newton < hi { newton } else { 0.5 * (lo + hi) };
+        if (next - x).abs() <= 1e-12 * (1.0 + x.abs()) {
+            return next;
+        }
+        x = next;
+    }
+    x
+}
+
+// Real roots of the polynomial with coefficients `poly` (lowest degree first) inside
+// [lo, hi], in increasing order. The roots of the derivative split the interval into
+// pieces on which the polynomial is monotonic, so each holds at most one root and it
+// can be bracketed; this stays accurate where closed-form quartic solutions cancel badly.
+fn roots_in(poly: &[f64], lo: f64, hi: f64) -> Vec<f64> {
+    let mut roots = Vec::new();
+    match poly.len() {
+        0 | 1 => {}
+        2 => {
+            if poly[1] != 0.0 {
+                let x = -poly[0] / poly[1];
+                if x >= lo && x <= hi {
+                    roots.push(x);
+                }
+            }
+        }
+        _ => {
+            let derivative: Vec<f64> = poly.iter().enumerate().skip(1).map(|(i, c)| c * i as f64).collect();
+            let mut bounds = vec![lo];
+            bounds.extend(roots_in(&derivative, lo, hi));
+            bounds.push(hi);
+
+            for pair in bounds.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                let (fa, fb) = (evaluate(poly, a), evaluate(poly, b));
+                if fa == 0.0 {
+                    if roots.last() != Some(&a) {
+                        roots.push(a);
+                    }
+                } else if fa.signum() != fb.signum() && fb != 0.0 {
+                    roots.push(refine_root(poly, &derivative, a, b));
+                }
+            }
+            if evaluate(poly, hi) == 0.0 && roots.last() != Some(&hi) {
+                roots.push(hi);
+            }
+        }
+    }
+    roots
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        // Work in f64 in the torus frame, with a unit direction so the coefficients stay balanced.
+        let length = ray.direction().length() as f64;
+        let mut o = self.to_local(ray.origin() - self.center);
+        let d = self.to_local(ray.direction()).map(|c| c / length);
+        let (big_r, small_r) = (self.major_radius as f64, self.minor_radius as f64);
+
+        // Clip to the bounding sphere, and move the origin to where the search starts so that
+        // distant rays don't lose precision.
+        let outer = big_r + small_r;
+        let b = o[0] * d[0] + o[1] * d[1] + o[2] * d[2];
+        let c = o[0] * o[0] + o[1] * o[1] + o[2] * o[2] - outer * outer;
+        let discriminant = b * b - c;
+        if discriminant <= 0.0 {
+            return None;
+        }
+        let lo = (-b - discriminant.sqrt()).max(t_min as f64 * length);
+        let hi = (-b + discriminant.sqrt()).min(t_max as f64 * length);
+        if lo >= hi {
+            return None;
+        }
+        for i in 0..3 {
+            o[i] += d[i] * lo;
+        }
+
+        // |p|^2 + R^2 - r^2 = 2R sqrt(px^2 + pz^2), squared and expanded along p = o + t d.
+        let e = o[0] * o[0] + o[1] * o[1] + o[2] * o[2] + big_r * big_r - small_r * small_r;
+        let f = o[0] * d[0] + o[1] * d[1] + o[2] * d[2];
+        let g = d[0] * d[0] + d[2] * d[2];
+        let h = o[0] * d[0] + o[2] * d[2];
+        let k = o[0] * o[0] + o[2] * o[2];
+        let four_r2 = 4.0 * big_r * big_r;
+        let poly = [
+            e * e - four_r2 * k,
+            4.0 * f * e - 2.0 * four_r2 * h,
+            4.0 * f * f + 2.0 * e - four_r2 * g,
+            4.0 * f,
+            1.0,
+        ];
+
+        // Compare in f32, where the caller's bounds live, so a root at t_min is never returned.
+        let s = roots_in(&poly, 0.0, hi - lo).into_iter().find(|&s| ((s + lo) / length) as f32 > t_min)?;
+        let t = ((s + lo) / length) as f32;
+        if t >= t_max {
+            return None;
+        }
+
+        // The normal points away from the nearest point on the center circle.
+        let p = [o[0] + d[0] * s, o[1] + d[1] * s, o[2] + d[2] * s];
+        let ring = big_r / (p[0] * p[0] + p[2] * p[2]).sqrt();
+        let local = [p[0] * (1.0 - ring), p[1], p[2] * (1.0 - ring)];
+        let normal = (self.u * local[0] as f32 + self.axis * local[1] as f32 + self.w * local[2] as f32).unit_vector();
+
+        Some(HitRecord {
+            t,
+            p: ray.at(t),
+            normal,
+            u: 0.0,
+            v: 0.0,
+            uv: None,
+            color: None,
+            material: self.material.as_ref(),
+        })
+    }
+
+    fn bounding_box(&self) -> BBox {
+        // The center circle's extent along each world axis, grown by the tube.
+        let mut extent = Vec3::new(0.0, 0.0, 0.0);
+        for i in 0..3 {
+            extent[i] = self.major_radius * (1.0 - self.axis[i] * self.axis[i]).max(0.0).sqrt() + self.minor_radius;
+        }
+        BBox::from_points(self.center - extent, self.center + extent)
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        surface_spans(self, ray)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn assert_roots(found: Vec<f64>, expected: &[f64], tolerance: f64) {
+        assert_eq!(found.len(), expected.len(), "{:?}", found);
+        for (x, e) in found.iter().zip(expected) {
+            assert!((x - e).abs() < tolerance, "{:?} != {:?}", found, expected);
+        }
+    }
+
+    fn torus() -> Torus {
+        let material = Box::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        Torus::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 2.0, 0.5, material)
+    }
+
+    #[test]
+    fn refine_root_finds_rising_and_falling_roots() {
+        let rising = [-2.0, 0.0, 1.0];
+        let falling = [2.0, 0.0, -1.0];
+        assert!((refine_root(&rising, &[0.0, 2.0], 1.0, 2.0) - 2f64.sqrt()).abs() < 1e-12);
+        assert!((refine_root(&falling, &[0.0, -2.0], 1.0, 2.0) - 2f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn roots_in_finds_simple_roots() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        let poly = [24.0, -50.0, 35.0, -10.0, 1.0];
+        assert_roots(roots_in(&poly, 0.0, 5.0), &[1.0, 2.0, 3.0, 4.0], 1e-9);
+        assert_roots(roots_in(&poly, 1.5, 3.5), &[2.0, 3.0], 1e-9);
+        assert_roots(roots_in(&poly, 4.5, 9.0), &[], 0.0);
+    }
+
+    #[test]
+    fn roots_in_finds_repeated_roots_once() {
+        // (x - 1)^2 (x - 3)(x - 4)
+        assert_roots(roots_in(&[12.0, -31.0, 27.0, -9.0, 1.0], 0.0, 5.0), &[1.0, 3.0, 4.0], 1e-6);
+        // (x - 2)^4
+        assert_roots(roots_in(&[16.0, -32.0, 24.0, -8.0, 1.0], 0.0, 5.0), &[2.0], 1e-6);
+    }
+
+    #[test]
+    fn rays_through_the_hole_cross_the_tube_twice() {
+        let ray = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        let torus = torus();
+        let spans = torus.spans(&ray);
+        assert_eq!(spans.len(), 2);
+        for (span, [enter, exit]) in spans.iter().zip([[2.5, 3.5], [6.5, 7.5]]) {
+            assert!((span.enter.t - enter).abs() < 1e-4 && (span.exit.t - exit).abs() < 1e-4);
+            assert!(span.enter.normal.dot(ray.direction()) < -0.99);
+            assert!(span.exit.normal.dot(ray.direction()) > 0.99);
+        }
+    }
+
+    #[test]
+    fn grazing_rays_hit_near_the_tangent_point_or_miss() {
+        let torus = torus();
+        let hit = |origin: Vec3| torus.hit(&Ray::new(origin, Vec3::new(1.0, 0.0, 0.0)), 0.001, f32::INFINITY);
+
+        // Just below the top of the tube the two roots are close but distinct.
+        let rec = hit(Vec3::new(-5.0, 0.4999, 0.0)).unwrap();
+        assert!((rec.t - 3.0).abs() < 0.02 && rec.normal.y > 0.99);
+        assert!(hit(Vec3::new(-5.0, 0.5001, 0.0)).is_none());
+
+        // Exactly tangent to the top of the tube and to the outer rim: a double root.
+        for (origin, t) in [(Vec3::new(-5.0, 0.5, 0.0), 3.0), (Vec3::new(-5.0, 0.0, 2.5), 5.0)] {
+            if let Some(rec) = hit(origin) {
+                assert!((rec.t - t).abs() < 0.02);
+            }
+            // Walking the hits must still end.
+            torus.spans(&Ray::new(origin, Vec3::new(1.0, 0.0, 0.0)));
+        }
+    }
+}
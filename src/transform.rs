@@ -0,0 +1,218 @@
+use std::ops;
+use crate::vec3::Vec3;
+
+// A 4x4 matrix acting on column vectors, stored by rows: m[row][column].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mat4 {
+    pub m: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+    pub fn identity() -> Mat4 {
+        Mat4 {
+            m: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    // For formats such as glTF that store matrices column by column.
+    pub fn from_columns(values: [f32; 16]) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, value) in values.iter().enumerate() {
+            m[i % 4][i / 4] = *value;
+        }
+        Mat4 { m }
+    }
+
+    pub fn translation(t: Vec3) -> Mat4 {
+        let mut result = Mat4::identity();
+        result.m[0][3] = t.x;
+        result.m[1][3] = t.y;
+        result.m[2][3] = t.z;
+        result
+    }
+
+    pub fn scaling(s: Vec3) -> Mat4 {
+        let mut result = Mat4::identity();
+        result.m[0][0] = s.x;
+        result.m[1][1] = s.y;
+        result.m[2][2] = s.z;
+        result
+    }
+
+    // Rotation by `degrees` counter-clockwise around `axis` (Rodrigues' formula).
+    pub fn rotation(axis: Vec3, degrees: f32) -> Mat4 {
+        let a = axis.unit_vector();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+        Mat4 {
+            m: [
+                [t * a.x * a.x + cos, t * a.x * a.y - sin * a.z, t * a.x * a.z + sin * a.y, 0.0],
+                [t * a.x * a.y + sin * a.z, t * a.y * a.y + cos, t * a.y * a.z - sin * a.x, 0.0],
+                [t * a.x * a.z - sin * a.y, t * a.y * a.z + sin * a.x, t * a.z * a.z + cos, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    // Rotation by a unit quaternion (x, y, z, w).
+    pub fn from_quaternion(x: f32, y: f32, z: f32, w: f32) -> Mat4 {
+        Mat4 {
+            m: [
+                [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w), 0.0],
+                [2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w), 0.0],
+                [2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y), 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = self.m[column][row];
+            }
+        }
+        Mat4 { m }
+    }
+
+    // Determinant of the upper 3x3 part; negative when the matrix mirrors space.
+    pub fn determinant3(&self) -> f32 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    // Gauss-Jordan elimination with partial pivoting, in f64 to keep small scales accurate.
+    pub fn inverse(&self) -> Option<Mat4> {
+        let mut a = [[0.0f64; 8]; 4];
+        for (row, (augmented, values)) in a.iter_mut().zip(&self.m).enumerate() {
+            for (value, &m) in augmented.iter_mut().zip(values) {
+                *value = m as f64;
+            }
+            augmented[4 + row] = 1.0;
+        }
+
+        for column in 0..4 {
+            let pivot = (column..4).max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))?;
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(column, pivot);
+
+            let scale = 1.0 / a[column][column];
+            for value in a[column].iter_mut() {
+                *value *= scale;
+            }
+            for row in 0..4 {
+                if row != column {
+                    let factor = a[row][column];
+                    let pivot_row = a[column];
+                    for (value, p) in a[row].iter_mut().zip(pivot_row) {
+                        *value -= factor * p;
+                    }
+                }
+            }
+        }
+
+        let m = a.map(|row| [row[4], row[5], row[6], row[7]].map(|value| value as f32));
+        Some(Mat4 { m })
+    }
+
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3],
+            m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3],
+            m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3],
+        )
+    }
+
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
+impl ops::Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, rhs: Mat4) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[row][k] * rhs.m[k][column]).sum();
+            }
+        }
+        Mat4 { m }
+    }
+}
+
+// An invertible affine transform, with the inverse kept alongside for mapping rays
+// into object space and the inverse transpose for mapping normals back out.
+#[derive(Copy, Clone, Debug)]
+pub struct Transform {
+    pub matrix: Mat4,
+    pub inverse: Mat4,
+    pub inverse_transpose: Mat4,
+}
+
+impl Transform {
+    pub fn new(matrix: Mat4) -> Option<Transform> {
+        let inverse = matrix.inverse()?;
+        Some(Transform { matrix, inverse, inverse_transpose: inverse.transpose() })
+    }
+
+    // Scale first, then rotate around the x, y and z axes in that order (degrees), then translate.
+    pub fn from_trs(translate: Vec3, rotate: Vec3, scale: Vec3) -> Option<Transform> {
+        let rotation = Mat4::rotation(Vec3::new(0.0, 0.0, 1.0), rotate.z)
+            * Mat4::rotation(Vec3::new(0.0, 1.0, 0.0), rotate.y)
+            * Mat4::rotation(Vec3::new(1.0, 0.0, 0.0), rotate.x);
+        Transform::new(Mat4::translation(translate) * rotation * Mat4::scaling(scale))
+    }
+
+    pub fn point(&self, p: Vec3) -> Vec3 {
+        self.matrix.transform_point(p)
+    }
+
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        self.inverse_transpose.transform_vector(n).unit_vector()
+    }
+
+    pub fn is_mirroring(&self) -> bool {
+        self.matrix.determinant3() < 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverse_undoes_the_matrix() {
+        let matrix = Mat4::translation(Vec3::new(1.0, -2.0, 3.0))
+            * Mat4::rotation(Vec3::new(1.0, 1.0, 0.0), 30.0)
+            * Mat4::scaling(Vec3::new(2.0, 0.5, -1.0));
+        let product = matrix * matrix.inverse().unwrap();
+        for (row, values) in product.m.iter().enumerate() {
+            for (column, value) in values.iter().enumerate() {
+                let expected = if row == column { 1.0 } else { 0.0 };
+                assert!((value - expected).abs() < 1e-5, "{} at ({}, {})", value, row, column);
+            }
+        }
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        assert!(Mat4::scaling(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+}
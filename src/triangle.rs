@@ -0,0 +1,240 @@
+use crate::bounds::BBox;
+use crate::hits::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+pub struct Triangle {
+    pub vertices: [Vec3; 3],
+    pub normal: Vec3,
+    pub material: Box<dyn Material>,
+}
+
+impl Triangle {
+    // Counter-clockwise vertices face the viewer.
+    pub fn new(a: Vec3, b: Vec3, c: Vec3, material: Box<dyn Material>) -> Triangle {
+        let normal = (b - a).cross(c - a).unit_vector();
+        Triangle { vertices: [a, b, c], normal, material }
+    }
+}
+
+fn max_dimension(v: Vec3) -> usize {
+    let (x, y, z) = (v.x.abs(), v.y.abs(), v.z.abs());
+    if x > y && x > z {
+        0
+    } else if y > z {
+        1
+    } else {
+        2
+    }
+}
+
+// Watertight ray/triangle intersection (Woop, Benthin and Wald, JCGT 2013). The triangle is
+// sheared into a space where the ray runs along +z from the origin, so the edge tests of two
+// triangles sharing an edge evaluate the same expressions and no ray can slip between them.
+// A ray exactly through a shared edge or vertex is given to just one of the triangles.
+// Returns the distance along the ray and the barycentric weights of `b` and `c`.
+pub fn intersect(ray: &Ray, a: Vec3, b: Vec3, c: Vec3, t_min: f32, t_max: f32) -> Option<(f32, f32, f32)> {
+    let dir = ray.direction();
+
+    let kz = max_dimension(dir);
+    let mut kx = (kz + 1) % 3;
+    let mut ky = (kx + 1) % 3;
+    // Keep the winding of the projected triangle consistent.
+    if dir[kz] < 0.0 {
+        std::mem::swap(&mut kx, &mut ky);
+    }
+
+    let sx = dir[kx] / dir[kz];
+    let sy = dir[ky] / dir[kz];
+    let sz = 1.0 / dir[kz];
+
+    let a = a - ray.origin();
+    let b = b - ray.origin();
+    let c = c - ray.origin();
+
+    let ax = a[kx] - sx * a[kz];
+    let ay = a[ky] - sy * a[kz];
+    let bx = b[kx] - sx * b[kz];
+    let by = b[ky] - sy * b[kz];
+    let cx = c[kx] - sx * c[kz];
+    let cy = c[ky] - sy * c[kz];
+
+    let mut u = cx * by - cy * bx;
+    let mut v = ax * cy - ay * cx;
+    let mut w = bx * ay - by * ax;
+
+    // Exactly zero means the ray grazes an edge; settle it in double precision.
+    if u == 0.0 || v == 0.0 || w == 0.0 {
+        u = (cx as f64 * by as f64 - cy as f64 * bx as f64) as f32;
+        v = (ax as f64 * cy as f64 - ay as f64 * cx as f64) as f32;
+        w = (bx as f64 * ay as f64 - by as f64 * ax as f64) as f32;
+    }
+
+    if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) {
+        return None;
+    }
+
+    let det = u + v + w;
+    if det == 0.0 {
+        return None;
+    }
+
+    // An edge the ray passes exactly through belongs to the triangle that, facing the ray,
+    // runs it upwards (or rightwards when level). Its neighbour runs it the other way, and
+    // around a vertex exactly one triangle owns both edges that meet there.
+    let owns = |edge: f32, (px, py): (f32, f32), (qx, qy): (f32, f32)| {
+        let (dx, dy) = if det > 0.0 { (qx - px, qy - py) } else { (px - qx, py - qy) };
+        edge != 0.0 || dy > 0.0 || (dy == 0.0 && dx > 0.0)
+    };
+    if !owns(u, (bx, by), (cx, cy)) || !owns(v, (cx, cy), (ax, ay)) || !owns(w, (ax, ay), (bx, by)) {
+        return None;
+    }
+
+    let az = sz * a[kz];
+    let bz = sz * b[kz];
+    let cz = sz * c[kz];
+    let t = (u * az + v * bz + w * cz) / det;
+    if t <= t_min || t >= t_max {
+        return None;
+    }
+
+    Some((t, v / det, w / det))
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let [a, b, c] = self.vertices;
+        let (t, u, v) = intersect(ray, a, b, c, t_min, t_max)?;
+
+        Some(HitRecord {
+            t,
+            p: ray.at(t),
+            normal: self.normal,
+            u,
+            v,
+            uv: None,
+            color: None,
+            material: self.material.as_ref(),
+        })
+    }
+
+    fn bounding_box(&self) -> BBox {
+        let [a, b, c] = self.vertices;
+        BBox::surrounding(&BBox::from_points(a, b), &BBox::from_points(c, c)).pad()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hits(triangles: &[[Vec3; 3]], ray: &Ray) -> usize {
+        triangles
+            .iter()
+            .filter(|[a, b, c]| intersect(ray, *a, *b, *c, 0.001, f32::INFINITY).is_some())
+            .count()
+    }
+
+    // Rays from above and below the z = 0 plane through `target`, with directions whose
+    // shear is exact so the target lands exactly on the edge or vertex being tested.
+    fn rays_through(target: Vec3) -> Vec<Ray> {
+        let mut rays = Vec::new();
+        for z in [4.0, -4.0] {
+            for x in -3..=3 {
+                for y in -3..=3 {
+                    let origin = target + Vec3::new(x as f32, y as f32, z);
+                    rays.push(Ray::new(origin, target - origin));
+                }
+            }
+        }
+        rays
+    }
+
+    #[test]
+    fn shared_edges_belong_to_one_triangle() {
+        let [p0, p1, p2, p3] = [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        ];
+        // Same winding, then the second triangle flipped over.
+        for square in [[[p0, p1, p2], [p0, p2, p3]], [[p0, p1, p2], [p0, p3, p2]]] {
+            for i in 1..8 {
+                let target = Vec3::new(i as f32 / 8.0, i as f32 / 8.0, 0.0);
+                for ray in rays_through(target) {
+                    assert_eq!(hits(&square, &ray), 1, "{:?} {:?}", target, ray.direction());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shared_vertices_belong_to_one_triangle() {
+        let rim = [(2, 0), (2, 2), (0, 2), (-2, 2), (-2, 0), (-2, -2), (0, -2), (2, -2)]
+            .map(|(x, y)| Vec3::new(x as f32, y as f32, 0.0));
+        let center = Vec3::new(0.0, 0.0, 0.0);
+        let fan: Vec<[Vec3; 3]> = (0..rim.len()).map(|i| [center, rim[i], rim[(i + 1) % rim.len()]]).collect();
+
+        for ray in rays_through(center) {
+            assert_eq!(hits(&fan, &ray), 1, "{:?}", ray.direction());
+        }
+        // So are the spokes between them.
+        for target in [Vec3::new(1.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0), Vec3::new(-0.5, -0.5, 0.0)] {
+            for ray in rays_through(target) {
+                assert_eq!(hits(&fan, &ray), 1, "{:?} {:?}", target, ray.direction());
+            }
+        }
+    }
+
+    #[test]
+    fn barycentric_weights_locate_the_hit() {
+        let (a, b, c) = (Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0));
+        let ray = Ray::new(Vec3::new(0.5, 0.25, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let (t, u, v) = intersect(&ray, a, b, c, 0.001, f32::INFINITY).unwrap();
+        assert!((t - 1.0).abs() < 1e-6);
+        assert!((u - 0.25).abs() < 1e-6 && (v - 0.125).abs() < 1e-6);
+
+        let triangle = Triangle::new(a, b, c, Box::new(crate::material::Lambertian::new(Vec3::new(1.0, 1.0, 1.0))));
+        let hit = triangle.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        let interpolated = a * (1.0 - hit.u - hit.v) + b * hit.u + c * hit.v;
+        assert!((hit.p - Vec3::new(0.5, 0.25, 0.0)).length() < 1e-6);
+        assert!((hit.p - interpolated).length() < 1e-6);
+        assert_eq!(hit.normal, Vec3::new(0.0, 0.0, 1.0));
+
+        assert!(intersect(&ray, a, b, c, 0.001, 1.0).is_none());
+        assert!(intersect(&ray, a, b, c, 1.0, f32::INFINITY).is_none());
+    }
+
+    #[test]
+    fn degenerate_triangles_are_never_hit() {
+        let line = [Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0), Vec3::new(2.0, 2.0, 0.0)];
+        let point = [Vec3::new(1.0, 1.0, 0.0); 3];
+        for target in [Vec3::new(0.5, 0.5, 0.0), Vec3::new(1.0, 1.0, 0.0)] {
+            for ray in rays_through(target) {
+                assert_eq!(hits(&[line, point], &ray), 0);
+            }
+        }
+        // Nor is a triangle seen exactly edge-on.
+        let ray = Ray::new(Vec3::new(-1.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        let flat = [Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 0.0)];
+        assert_eq!(hits(&[flat], &ray), 0);
+    }
+
+    #[test]
+    fn near_zero_edges_are_settled_in_double_precision() {
+        // Seen along +z from the origin, the edge from b to c passes the origin on the side of
+        // `inside` by e^2, which single precision rounds away to exactly zero.
+        let e = f32::EPSILON;
+        let b = Vec3::new(-1.0, -(1.0 + e), 1.0);
+        let c = Vec3::new(1.0 + e, 1.0 + 2.0 * e, 1.0);
+        let inside = Vec3::new(-1.0, 1.0, 1.0);
+        let outside = Vec3::new(1.0, -1.0, 1.0);
+        assert_eq!(c.x * b.y - c.y * b.x, 0.0);
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(intersect(&ray, inside, b, c, 0.001, f32::INFINITY).is_some());
+        assert!(intersect(&ray, outside, c, b, 0.001, f32::INFINITY).is_none());
+    }
+}
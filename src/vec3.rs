@@ -1,7 +1,7 @@
 use std::fmt;
 use std::ops;
 use std::ops::{DivAssign, MulAssign};
-use rand::{rng, Rng};
+use crate::random;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Vec3 { pub x: f32, pub y: f32, pub z: f32, }
@@ -12,11 +12,10 @@ impl Vec3 {
     }
 
     pub fn random_range(min: f32, max: f32) -> Vec3 {
-        let mut rng = rng();
         Vec3::new(
-            rng.random_range(min..max),
-            rng.random_range(min..max),
-            rng.random_range(min..max)
+            random::random_range(min, max),
+            random::random_range(min, max),
+            random::random_range(min, max)
         )
     }
 
@@ -30,11 +29,10 @@ impl Vec3 {
     }
 
     pub fn random_in_unit_disk() -> Vec3 {
-        let mut rng = rng();
         loop {
             let p = Vec3::new(
-                rng.random_range(-1.0..1.0),
-                rng.random_range(-1.0..1.0),
+                random::random_range(-1.0, 1.0),
+                random::random_range(-1.0, 1.0),
                 0.0
             );
             if p.sqr_length() < 1.0 {